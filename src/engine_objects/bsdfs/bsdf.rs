@@ -0,0 +1,41 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::Color};
+
+pub struct BsdfSample {
+    // Direction from the shaded point towards where the light comes from
+    pub direction: Unit<Vector3<Float>>,
+    // BSDF times the cosine divided by the pdf, the factor to multiply the traced light by
+    pub weight: Color,
+}
+
+// Describes how a surface scatters light. `outgoing` points towards the viewer and `incoming`
+// towards the light, both away from the surface.
+pub trait Bsdf {
+    // Fraction of the irradiance from `incoming` that is scattered towards `outgoing` per steradian
+    fn eval(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, incoming: &Unit<Vector3<Float>>) -> Color;
+
+    // Picks an incoming direction by (u, v) in [0, 1)², roughly in proportion to the BSDF
+    fn sample(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, u: Float, v: Float) -> Option<BsdfSample>;
+
+    // Solid angle density with which `sample` picks the incoming direction
+    fn pdf(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, incoming: &Unit<Vector3<Float>>) -> f64;
+}
+
+// Two tangents that together with the normal form an orthonormal basis
+pub fn tangent_frame(normal: &Vector3<Float>) -> (Vector3<Float>, Vector3<Float>) {
+    let helper = if normal.x.abs() > 0.9 { Vector3::y() } else { Vector3::x() };
+    let tangent = normal.cross(&helper).normalize();
+    let bitangent = normal.cross(&tangent);
+    return (tangent, bitangent);
+}
+
+// Samples the hemisphere around the normal with a density proportional to the cosine
+pub fn cosine_sample_hemisphere(normal: &Vector3<Float>, u: Float, v: Float) -> Unit<Vector3<Float>> {
+    let pi = std::f64::consts::PI as Float;
+    let (tangent, bitangent) = tangent_frame(normal);
+    let r = u.sqrt();
+    let phi = 2.0 * pi * v;
+    let z = (1.0 - u).max(0.0).sqrt();
+    return Unit::new_normalize(tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + normal * z);
+}
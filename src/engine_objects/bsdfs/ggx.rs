@@ -0,0 +1,88 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::Color};
+
+use super::{Bsdf, BsdfSample, tangent_frame};
+
+// Trowbridge-Reitz (GGX) distribution of microfacet normals, for the cosine between the microfacet
+// and the surface normal
+pub fn ggx_distribution(cos_halfway: f64, alpha: f64) -> f64 {
+    if cos_halfway <= 0.0 { return 0.0; }
+    let alpha2 = alpha * alpha;
+    let denominator = cos_halfway * cos_halfway * (alpha2 - 1.0) + 1.0;
+    return alpha2 / (std::f64::consts::PI * denominator * denominator);
+}
+
+// Smith masking for one direction, the fraction of microfacets facing it that are not hidden
+pub fn smith_masking(cos_theta: f64, alpha: f64) -> f64 {
+    let cos_theta = cos_theta.abs();
+    let alpha2 = alpha * alpha;
+    return 2.0 * cos_theta / (cos_theta + (alpha2 + (1.0 - alpha2) * cos_theta * cos_theta).sqrt());
+}
+
+// Samples a microfacet normal with a density of D(h)·cos(θh)
+pub fn sample_ggx_normal(normal: &Vector3<Float>, alpha: f64, u: Float, v: Float) -> Unit<Vector3<Float>> {
+    let (tangent, bitangent) = tangent_frame(normal);
+    let u = u as f64;
+    let cos_theta = ((1.0 - u) / (u * (alpha * alpha - 1.0) + 1.0)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * std::f64::consts::PI * v as f64;
+    let local = Vector3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta).map(|x| x as Float);
+    return Unit::new_normalize(tangent * local.x + bitangent * local.y + normal * local.z);
+}
+
+// Schlick's approximation of the Fresnel reflectance
+pub fn fresnel_schlick(f0: Color, cos_theta: f64) -> Color {
+    let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    return f0 * (1.0 - weight) + Color::white() * weight;
+}
+
+// Specular reflection off a rough surface made of tiny mirrors, with Cook-Torrance shadowing and
+// Fresnel. `f0` is the reflectance at normal incidence and `alpha` the width of the distribution.
+pub struct Ggx {
+    pub f0: Color,
+    pub alpha: f64,
+}
+
+impl Bsdf for Ggx {
+    fn eval(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, incoming: &Unit<Vector3<Float>>) -> Color {
+        let cos_outgoing = normal.dot(outgoing) as f64;
+        let cos_incoming = normal.dot(incoming) as f64;
+        if cos_outgoing <= 0.0 || cos_incoming <= 0.0 { return Color::black(); }
+
+        let halfway = (outgoing.into_inner() + incoming.into_inner()).normalize();
+        let distribution = ggx_distribution(normal.dot(&halfway) as f64, self.alpha);
+        let masking = smith_masking(cos_outgoing, self.alpha) * smith_masking(cos_incoming, self.alpha);
+        let fresnel = fresnel_schlick(self.f0, outgoing.dot(&halfway) as f64);
+        return fresnel * (distribution * masking / (4.0 * cos_outgoing * cos_incoming));
+    }
+
+    fn sample(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, u: Float, v: Float) -> Option<BsdfSample> {
+        let cos_outgoing = normal.dot(outgoing) as f64;
+        if cos_outgoing <= 0.0 { return None; }
+
+        let halfway = sample_ggx_normal(normal, self.alpha, u, v);
+        let cos_outgoing_halfway = outgoing.dot(&halfway);
+        if cos_outgoing_halfway <= 0.0 { return None; }
+
+        let direction = Unit::new_normalize(halfway.scale(2.0 * cos_outgoing_halfway) - outgoing.into_inner());
+        let cos_incoming = normal.dot(&direction) as f64;
+        if cos_incoming <= 0.0 { return None; }
+
+        let pdf = self.pdf(normal, outgoing, &direction);
+        if pdf <= 0.0 { return None; }
+
+        return Some(BsdfSample {
+            direction,
+            weight: self.eval(normal, outgoing, &direction) * (cos_incoming / pdf),
+        });
+    }
+
+    fn pdf(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, incoming: &Unit<Vector3<Float>>) -> f64 {
+        let halfway = (outgoing.into_inner() + incoming.into_inner()).normalize();
+        let cos_halfway = normal.dot(&halfway) as f64;
+        let cos_outgoing_halfway = outgoing.dot(&halfway).abs() as f64;
+        if cos_outgoing_halfway <= 0.0 { return 0.0; }
+        return ggx_distribution(cos_halfway, self.alpha) * cos_halfway / (4.0 * cos_outgoing_halfway);
+    }
+}
@@ -0,0 +1,33 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::Color};
+
+use super::{Bsdf, BsdfSample, cosine_sample_hemisphere};
+
+// Ideal diffuse reflection, scattering light evenly over the hemisphere
+pub struct Lambert {
+    pub albedo: Color,
+}
+
+impl Bsdf for Lambert {
+    fn eval(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, incoming: &Unit<Vector3<Float>>) -> Color {
+        if normal.dot(outgoing) <= 0.0 || normal.dot(incoming) <= 0.0 { return Color::black(); }
+        return self.albedo * (1.0 / std::f64::consts::PI);
+    }
+
+    fn sample(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, u: Float, v: Float) -> Option<BsdfSample> {
+        if normal.dot(outgoing) <= 0.0 { return None; }
+        let direction = cosine_sample_hemisphere(normal, u, v);
+        let pdf = self.pdf(normal, outgoing, &direction);
+        if pdf <= 0.0 { return None; }
+
+        return Some(BsdfSample {
+            direction,
+            weight: self.albedo,
+        });
+    }
+
+    fn pdf(self: &Self, normal: &Vector3<Float>, _outgoing: &Unit<Vector3<Float>>, incoming: &Unit<Vector3<Float>>) -> f64 {
+        return (normal.dot(incoming).max(0.0) as f64) / std::f64::consts::PI;
+    }
+}
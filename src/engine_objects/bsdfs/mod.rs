@@ -0,0 +1,10 @@
+mod bsdf;
+pub use bsdf::*;
+mod lambert;
+pub use lambert::*;
+mod phong;
+pub use phong::*;
+mod ggx;
+pub use ggx::*;
+mod roughdielectric;
+pub use roughdielectric::*;
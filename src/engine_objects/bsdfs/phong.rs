@@ -0,0 +1,45 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::Color};
+
+use super::{Bsdf, BsdfSample, cosine_sample_hemisphere};
+
+// Lambert diffuse with a normalized Blinn-Phong highlight on top
+pub struct Phong {
+    pub diffuse_color: Color,
+    pub specular_color: Color,
+    // Exponent of the highlight lobe, the highlight is left out when zero
+    pub shininess: f64,
+}
+
+impl Bsdf for Phong {
+    fn eval(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, incoming: &Unit<Vector3<Float>>) -> Color {
+        if normal.dot(incoming) <= 0.0 { return Color::black(); }
+        let mut color = self.diffuse_color * (1.0 / std::f64::consts::PI);
+
+        // Normalized so sharper highlights get brighter rather than losing energy
+        if self.shininess > 0.0 {
+            let halfway = (incoming.into_inner() + outgoing.into_inner()).normalize();
+            let alignment = normal.dot(&halfway).max(0.0) as f64;
+            let normalization = (self.shininess + 8.0) / (8.0 * std::f64::consts::PI);
+            color += self.specular_color * (normalization * alignment.powf(self.shininess));
+        }
+        return color;
+    }
+
+    // Only the diffuse lobe is importance sampled
+    fn sample(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, u: Float, v: Float) -> Option<BsdfSample> {
+        let direction = cosine_sample_hemisphere(normal, u, v);
+        let pdf = self.pdf(normal, outgoing, &direction);
+        if pdf <= 0.0 { return None; }
+
+        return Some(BsdfSample {
+            direction,
+            weight: self.eval(normal, outgoing, &direction) * (normal.dot(&direction) as f64 / pdf),
+        });
+    }
+
+    fn pdf(self: &Self, normal: &Vector3<Float>, _outgoing: &Unit<Vector3<Float>>, incoming: &Unit<Vector3<Float>>) -> f64 {
+        return (normal.dot(incoming).max(0.0) as f64) / std::f64::consts::PI;
+    }
+}
@@ -0,0 +1,145 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::{Color, random_float}};
+
+use super::{Bsdf, BsdfSample, ggx_distribution, sample_ggx_normal, smith_masking};
+
+// Fraction of light reflected at a dielectric interface for the cosine on the side the light
+// arrives from, `eta` being the index of refraction of the inside over the outside
+pub fn fresnel_dielectric(cos_theta: f64, eta: f64) -> f64 {
+    let (cos_i, eta) = if cos_theta < 0.0 { (-cos_theta, 1.0 / eta) } else { (cos_theta, eta) };
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 { return 1.0; }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    return (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0;
+}
+
+// Refracts a direction pointing away from the surface through the interface with the given normal.
+// Returns None on total internal reflection.
+fn refract(direction: &Unit<Vector3<Float>>, normal: &Vector3<Float>, eta: f64) -> Option<Unit<Vector3<Float>>> {
+    let mut cos_i = normal.dot(direction) as f64;
+    let mut eta = eta;
+    let mut normal = *normal;
+    if cos_i < 0.0 {
+        eta = 1.0 / eta;
+        cos_i = -cos_i;
+        normal = -normal;
+    }
+
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 { return None; }
+    let cos_t = (1.0 - sin2_t).sqrt();
+
+    let transmitted = -direction.into_inner() / eta as Float + normal * (cos_i / eta - cos_t) as Float;
+    return Some(Unit::new_normalize(transmitted));
+}
+
+// Glass with a rough surface, reflecting and transmitting through GGX microfacets as described by
+// Walter et al., "Microfacet Models for Refraction through Rough Surfaces" (2007). Directions can be
+// on either side of the normal, which points to the outside.
+pub struct RoughDielectric {
+    // Index of refraction of the inside over the outside
+    pub eta: f64,
+    pub alpha: f64,
+}
+
+impl RoughDielectric {
+    // Microfacet normal between the two directions facing the outside, with the relative index
+    // of refraction along the path, None when the pair cannot be connected
+    fn halfway(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, incoming: &Unit<Vector3<Float>>) -> Option<(Vector3<Float>, f64)> {
+        let cos_outgoing = normal.dot(outgoing);
+        let cos_incoming = normal.dot(incoming);
+        if cos_outgoing == 0.0 || cos_incoming == 0.0 { return None; }
+
+        let reflect = cos_outgoing * cos_incoming > 0.0;
+        let relative_eta = if reflect { 1.0 } else if cos_outgoing > 0.0 { self.eta } else { 1.0 / self.eta };
+
+        let mut halfway = incoming.into_inner() * relative_eta as Float + outgoing.into_inner();
+        if halfway.magnitude_squared() == 0.0 { return None; }
+        halfway = halfway.normalize();
+        if halfway.dot(normal) < 0.0 { halfway = -halfway; }
+
+        // Microfacets facing away from either direction do not contribute
+        if halfway.dot(incoming) * cos_incoming < 0.0 || halfway.dot(outgoing) * cos_outgoing < 0.0 { return None; }
+        return Some((halfway, relative_eta));
+    }
+}
+
+impl Bsdf for RoughDielectric {
+    fn eval(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, incoming: &Unit<Vector3<Float>>) -> Color {
+        let (halfway, relative_eta) = match self.halfway(normal, outgoing, incoming) {
+            None => { return Color::black(); }
+            Some(halfway) => halfway,
+        };
+
+        let cos_outgoing = normal.dot(outgoing) as f64;
+        let cos_incoming = normal.dot(incoming) as f64;
+        let outgoing_halfway = outgoing.dot(&halfway) as f64;
+        let incoming_halfway = incoming.dot(&halfway) as f64;
+
+        let distribution = ggx_distribution(normal.dot(&halfway) as f64, self.alpha);
+        let masking = smith_masking(cos_outgoing, self.alpha) * smith_masking(cos_incoming, self.alpha);
+        let fresnel = fresnel_dielectric(outgoing_halfway, self.eta);
+
+        if cos_outgoing * cos_incoming > 0.0 {
+            return Color::white() * (distribution * masking * fresnel / (4.0 * cos_incoming * cos_outgoing).abs());
+        }
+
+        let denominator = (incoming_halfway + outgoing_halfway / relative_eta).powi(2) * cos_incoming * cos_outgoing;
+        let transmission = distribution * (1.0 - fresnel) * masking * (incoming_halfway * outgoing_halfway / denominator).abs();
+        // Radiance is compressed into the smaller solid angle of the denser medium
+        return Color::white() * (transmission / (relative_eta * relative_eta));
+    }
+
+    // Picks a microfacet, then reflects or refracts through it in proportion to its Fresnel term
+    fn sample(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, u: Float, v: Float) -> Option<BsdfSample> {
+        let cos_outgoing = normal.dot(outgoing);
+        if cos_outgoing == 0.0 { return None; }
+
+        let halfway = sample_ggx_normal(normal, self.alpha, u, v);
+        let outgoing_halfway = outgoing.dot(&halfway);
+        let reflectance = fresnel_dielectric(outgoing_halfway as f64, self.eta);
+
+        let direction = if (random_float() as f64) < reflectance {
+            let reflected = Unit::new_normalize(halfway.scale(2.0 * outgoing_halfway) - outgoing.into_inner());
+            if normal.dot(&reflected) * cos_outgoing <= 0.0 { return None; }
+            reflected
+        } else {
+            let refracted = refract(outgoing, &halfway, self.eta)?;
+            if normal.dot(&refracted) * cos_outgoing >= 0.0 { return None; }
+            refracted
+        };
+
+        let pdf = self.pdf(normal, outgoing, &direction);
+        if pdf <= 0.0 { return None; }
+
+        return Some(BsdfSample {
+            direction,
+            weight: self.eval(normal, outgoing, &direction) * (normal.dot(&direction).abs() as f64 / pdf),
+        });
+    }
+
+    fn pdf(self: &Self, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, incoming: &Unit<Vector3<Float>>) -> f64 {
+        let (halfway, relative_eta) = match self.halfway(normal, outgoing, incoming) {
+            None => { return 0.0; }
+            Some(halfway) => halfway,
+        };
+
+        let outgoing_halfway = outgoing.dot(&halfway) as f64;
+        let incoming_halfway = incoming.dot(&halfway) as f64;
+        let cos_halfway = normal.dot(&halfway) as f64;
+        let halfway_pdf = ggx_distribution(cos_halfway, self.alpha) * cos_halfway;
+        let reflectance = fresnel_dielectric(outgoing_halfway, self.eta);
+
+        if normal.dot(outgoing) * normal.dot(incoming) > 0.0 {
+            return halfway_pdf / (4.0 * outgoing_halfway.abs()) * reflectance;
+        }
+
+        // Change of variables from the microfacet normal to the refracted direction
+        let jacobian = incoming_halfway.abs() / (incoming_halfway + outgoing_halfway / relative_eta).powi(2);
+        return halfway_pdf * jacobian * (1.0 - reflectance);
+    }
+}
@@ -1,13 +1,15 @@
 use nalgebra::Vector3;
 
+use crate::Float;
+
 #[derive(Clone, Copy, Default)]
 pub struct AABB {
-    pub bmin: Vector3<f64>,
-    pub bmax: Vector3<f64>,
+    pub bmin: Vector3<Float>,
+    pub bmax: Vector3<Float>,
 }
 
 impl AABB {
-    pub fn grow_by_point(self: &mut Self, point: &Vector3<f64>) {
+    pub fn grow_by_point(self: &mut Self, point: &Vector3<Float>) {
         self.bmin = Vector3::new(
             self.bmin.x.min(point.x),
             self.bmin.y.min(point.y),
@@ -25,21 +27,21 @@ impl AABB {
         self.grow_by_point(&bounds.bmax);
     }
 
-    pub fn surface_area(self: &Self) -> f64 {
+    pub fn surface_area(self: &Self) -> Float {
         let d = self.bmax - self.bmin;
         return 2.0 * (d.x * d.y + d.y * d.z + d.z * d.x);
     }
 
     pub fn new() -> AABB {
         return AABB {
-            bmin: Vector3::new( std::f64::MAX,  std::f64::MAX,  std::f64::MAX),
-            bmax: Vector3::new(-std::f64::MAX, -std::f64::MAX, -std::f64::MAX),
+            bmin: Vector3::new( Float::MAX,  Float::MAX,  Float::MAX),
+            bmax: Vector3::new(-Float::MAX, -Float::MAX, -Float::MAX),
         }
     }
 
     pub fn reset(self: &mut Self) {
-        self.bmin = Vector3::new( std::f64::MAX,  std::f64::MAX,  std::f64::MAX);
-        self.bmax = Vector3::new(-std::f64::MAX, -std::f64::MAX, -std::f64::MAX);
+        self.bmin = Vector3::new( Float::MAX,  Float::MAX,  Float::MAX);
+        self.bmax = Vector3::new(-Float::MAX, -Float::MAX, -Float::MAX);
     }
 
     pub fn longest_axis(self: &Self) -> usize {
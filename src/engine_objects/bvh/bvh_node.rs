@@ -1,4 +1,4 @@
-use crate::{EPSILON, engine_objects::{Ray, primitives::Primitive}};
+use crate::{EPSILON, Float, engine_objects::{Ray, primitives::Primitive}};
 
 use super::{AABB, BVH};
 
@@ -14,13 +14,17 @@ pub struct BVHNode {
 
 impl BVHNode {
     pub fn subdivide_node(self: &mut Self, bvh: &mut BVH, primitives: &Vec<Box<dyn Primitive>>) {
+        self.is_leaf = true;
+
         if self.count <= 2 { return; }
 
-        self.is_leaf = true;
         self.left = bvh.pool_ptr;
         bvh.pool_ptr += 2;
 
-        if !self.partition_triangles(bvh, primitives) { return; }
+        if !self.partition_triangles(bvh, primitives) {
+            bvh.pool_ptr -= 2;
+            return;
+        }
 
         let mut left = bvh.pool[self.left];
         left.subdivide_node(bvh, primitives);
@@ -35,14 +39,14 @@ impl BVHNode {
 
     fn find_best_split(self: &mut Self, bvh: &BVH) -> Option<usize> {
         let mut best_bin_index = None;
-        let mut best_cost = std::f64::MAX;
-        let cur_cost = self.bounds.surface_area() * self.count as f64;
+        let mut best_cost = Float::MAX;
+        let cur_cost = self.bounds.surface_area() * self.count as Float;
 
         for i in 0..(bvh.bin_count - 1) {
             let bin_left = &bvh.bins_left[i];
             let bin_right = &bvh.bins_right[i];
 
-            let cost = bin_left.bounds.surface_area() * bin_left.count as f64 + bin_right.bounds.surface_area() * bin_right.count as f64;
+            let cost = bin_left.bounds.surface_area() * bin_left.count as Float + bin_right.bounds.surface_area() * bin_right.count as Float;
 
             if (cost < cur_cost) &&
                (bin_left.count > 0 && bin_right.count > 0) &&
@@ -56,9 +60,9 @@ impl BVHNode {
         return best_bin_index;
     }
     
-    fn update_triangles_nodes(self: &mut Self, bvh: &mut BVH, primitives: &Vec<Box<dyn Primitive>>, bin_index: usize, l_axis: usize, k1: f64, cbmin: f64) {
+    fn update_triangles_nodes(self: &mut Self, bvh: &mut BVH, primitives: &Vec<Box<dyn Primitive>>, bin_index: usize, l_axis: usize, k1: Float, cbmin: Float) {
         // Quicksort triangle indices
-        let j = self.first;
+        let mut j = self.first;
         for i in self.first..(self.first + self.count) {
             let primitive = &primitives[bvh.triangle_indices[i]];
             let ci = primitive.get_centroid()[l_axis];
@@ -68,6 +72,7 @@ impl BVHNode {
                 let tmp = bvh.triangle_indices[i];
                 bvh.triangle_indices[i] = bvh.triangle_indices[j];
                 bvh.triangle_indices[j] = tmp; 
+                j += 1;
             }
         }
 
@@ -98,12 +103,12 @@ impl BVHNode {
 
         bvh.reset_bins();
 
-        let cbmin: f64 = centroid_bounding_box.bmin[l_axis];
-        let cbmax: f64 = centroid_bounding_box.bmax[l_axis];
+        let cbmin: Float = centroid_bounding_box.bmin[l_axis];
+        let cbmax: Float = centroid_bounding_box.bmax[l_axis];
 
         if (cbmax - cbmin).abs() <= EPSILON { return false; }
 
-        let k1 = (bvh.bin_count as f64 * (1.0 - EPSILON)) / (cbmax - cbmin);
+        let k1 = (bvh.bin_count as Float * (1.0 - EPSILON)) / (cbmax - cbmin);
 
         // Fill the bins with triangles
         for i in self.first..(self.first + self.count) {
@@ -132,12 +137,12 @@ impl BVHNode {
         }
 
         // Do a pass through the bins from the right
-        bvh.bins_left[bvh.bin_count - 2] = bvh.bins[bvh.bin_count - 1];
+        bvh.bins_right[bvh.bin_count - 2] = bvh.bins[bvh.bin_count - 1];
         for i in (0..=(bvh.bin_count - 3)).rev() {
             let bin = &bvh.bins[i + 1];
-            let prev_bin_right = &bvh.bins_left[i + 1];
+            let prev_bin_right = &bvh.bins_right[i + 1];
             
-            let mut bin_right = bvh.bins_left[i];
+            let mut bin_right = bvh.bins_right[i];
             bin_right.count = prev_bin_right.count + bin.count;
             bin_right.bounds.grow_by_bounds(&prev_bin_right.bounds);
             bin_right.bounds.grow_by_bounds(&bin.bounds);
@@ -161,17 +166,18 @@ impl BVHNode {
         }
     }
 
-    fn intersect_triangles(self: &Self, ray: &Ray, primitives: &Vec<Box<dyn Primitive>>, triangle_indices: &Vec<usize>) -> Option<(usize, f64)> {
-        let mut nearest_intersection: Option<(usize, f64)> = None;
+    fn intersect_triangles(self: &Self, ray: &Ray, primitives: &Vec<Box<dyn Primitive>>, triangle_indices: &Vec<usize>, accept: &dyn Fn(usize, Float) -> bool) -> Option<(usize, Float)> {
+        let mut nearest_intersection: Option<(usize, Float)> = None;
 
         for i in 0..self.count {
-            let primitive_index = triangle_indices[i];
+            let primitive_index = triangle_indices[self.first + i];
             let primitive = &primitives[primitive_index];
             match primitive.intersect(ray) {
                 None => { continue; }
+                Some(distance) if !accept(primitive_index, distance) => { continue; }
                 Some(distance) => {
                     if let Some((_, min_distance)) = nearest_intersection {
-                        if distance < min_distance && distance > EPSILON {
+                        if distance < min_distance {
                             nearest_intersection = Some((primitive_index, distance));
                         }
                     } else {
@@ -184,12 +190,14 @@ impl BVHNode {
         return nearest_intersection;
     }
 
-    pub fn traverse(self: &Self, ray: &Ray, bvh: &BVH, primitives: &Vec<Box<dyn Primitive>>) -> Option<(usize, f64)> {
+    // Finds the nearest intersection that `accept` agrees with, which lets cut out parts of surfaces
+    // be skipped
+    pub fn traverse(self: &Self, ray: &Ray, bvh: &BVH, primitives: &Vec<Box<dyn Primitive>>, accept: &dyn Fn(usize, Float) -> bool) -> Option<(usize, Float)> {
         match ray.intersect_bounds(&self.bounds) {
             None => { return None; }
             Some(_) => {
                 if self.is_leaf {
-                    return self.intersect_triangles(ray, primitives, &bvh.triangle_indices);
+                    return self.intersect_triangles(ray, primitives, &bvh.triangle_indices, accept);
                 }
 
                 let left = &bvh.pool[self.left];
@@ -197,12 +205,42 @@ impl BVHNode {
 
                 let ray_dir_axis = ray.direction[self.split_axis];
 
-                if ray_dir_axis > 0.0 {
-                    return left.traverse(ray, bvh, primitives).or_else(|| right.traverse(ray, bvh, primitives));
-                } else {
-                    return right.traverse(ray, bvh, primitives).or_else(|| left.traverse(ray, bvh, primitives));
+                let (near, far) = if ray_dir_axis > 0.0 { (left, right) } else { (right, left) };
+
+                // Children can overlap, so the far child may still hold a nearer intersection
+                let near_intersection = near.traverse(ray, bvh, primitives, accept);
+                let far_intersection = far.traverse(ray, bvh, primitives, accept);
+
+                return match (near_intersection, far_intersection) {
+                    (Some(n), Some(f)) => if f.1 < n.1 { Some(f) } else { Some(n) },
+                    (n, f) => n.or(f),
+                };
+            }
+        }
+    }
+
+    // Whether any intersection that `accept` agrees with lies closer than the distance, stopping at
+    // the first one found as shadow rays only need to know that something blocks them
+    pub fn any_hit(self: &Self, ray: &Ray, bvh: &BVH, primitives: &Vec<Box<dyn Primitive>>, max_distance: Float, accept: &dyn Fn(usize, Float) -> bool) -> bool {
+        match ray.intersect_bounds(&self.bounds) {
+            None => { return false; }
+            Some(entry) if entry >= max_distance => { return false; }
+            Some(_) => {
+                if self.is_leaf {
+                    for i in 0..self.count {
+                        let primitive_index = bvh.triangle_indices[self.first + i];
+                        if let Some(distance) = primitives[primitive_index].intersect(ray) {
+                            if distance < max_distance && accept(primitive_index, distance) { return true; }
+                        }
+                    }
+                    return false;
                 }
+
+                let left = &bvh.pool[self.left];
+                let right = &bvh.pool[self.left + 1];
+                return left.any_hit(ray, bvh, primitives, max_distance, accept)
+                    || right.any_hit(ray, bvh, primitives, max_distance, accept);
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -1,64 +1,203 @@
-use minifb::{Key, Window};
-use nalgebra::{Matrix4, Vector3};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window};
+use nalgebra::{Rotation3, Unit, Vector2, Vector3};
+
+use crate::Float;
+
+use super::{Color, Controls, Projection, Ray, Scene, Screen, Stereo};
 
 pub struct Camera {
-    pub origin: Vector3<f64>,
-    pub forward: Vector3<f64>,
-    pub up: Vector3<f64>,
-    pub fov: f64,
+    pub origin: Vector3<Float>,
+    pub forward: Vector3<Float>,
+    pub up: Vector3<Float>,
+    pub fov: Float,
+    // Exposure compensation in stops, radiance is scaled by 2^exposure before display
+    pub exposure: f64,
+    pub projection: Projection,
+    // Renders both eyes into the frame when present
+    pub stereo: Option<Stereo>,
+    // Radius of the thin lens, a pinhole with everything in focus when zero
+    pub aperture: Float,
+    // Distance along the view direction of the plane that is in focus
+    pub focus_distance: Float,
+    // Number of diaphragm blades shaping the bokeh, a round aperture when fewer than three
+    pub aperture_blades: u32,
+    // Part of the frame's time the shutter is open for, from zero to one, rays are spread over it
+    pub shutter_open: Float,
+    pub shutter_close: Float,
+    // Distance the camera moves over the frame's time
+    pub motion: Vector3<Float>,
 }
 
 impl Camera {
-    pub fn update_input(self: &mut Self, window: &Window) {
-        // Movement speed
-        let speed = if window.is_key_down(Key::LeftShift) { 0.30 } else { 0.15 };
+    // Converts radiance arriving at the camera to the displayed pixel colour
+    pub fn expose(self: &Self, radiance: Color) -> Color {
+        return radiance * 2.0f64.powf(self.exposure);
+    }
 
-        // Translation
-        if window.is_key_down(Key::D) { self.translate_x(speed); }
-        if window.is_key_down(Key::A) { self.translate_x(-speed); }
-        if window.is_key_down(Key::R) { self.translate_y(speed); }
-        if window.is_key_down(Key::F) { self.translate_y(-speed); }
-        if window.is_key_down(Key::W) { self.translate_z(speed); }
-        if window.is_key_down(Key::S) { self.translate_z(-speed); }
-        // Rotation
-        if window.is_key_down(Key::Up)    { self.rotate_x(speed); }
-        if window.is_key_down(Key::Down)  { self.rotate_x(-speed); }
-        if window.is_key_down(Key::Right) { self.rotate_y(speed); }
-        if window.is_key_down(Key::Left)  { self.rotate_y(-speed); }
-        if window.is_key_down(Key::E)     { self.rotate_z(speed); }
-        if window.is_key_down(Key::Q)     { self.rotate_z(-speed); }
+    // Right, up and forward directions of the view, independent of the field of view so every
+    // projection can use them
+    pub fn axes(self: &Self) -> (Vector3<Float>, Vector3<Float>, Vector3<Float>) {
+        let forward: Vector3<Float> = self.forward.normalize();
+        let right: Vector3<Float> = self.up.cross(&forward).normalize();
+        let up: Vector3<Float> = forward.cross(&right);
+        return (right, up, forward);
     }
 
-    fn translate_x(self: &mut Self, dist: f64) {
-        let right = self.up.cross(&self.forward);
-        self.origin = self.origin + right * dist;
+    // Maps two uniform numbers to a point on the aperture, scaled to fit in the unit circle
+    pub fn sample_aperture(self: &Self, u: Float, v: Float) -> Vector2<Float> {
+        let pi = std::f64::consts::PI as Float;
+
+        if self.aperture_blades >= 3 {
+            // Picks one of the triangles between the centre and the edges of the polygon, then a
+            // uniform point inside it
+            let blades = self.aperture_blades as Float;
+            let blade = (u * blades).floor().min(blades - 1.0);
+            let u = u * blades - blade;
+            let corner = |index: Float| -> Vector2<Float> {
+                let angle = 2.0 * pi * index / blades;
+                return Vector2::new(angle.cos(), angle.sin());
+            };
+            let su = u.sqrt();
+            return (corner(blade) * (1.0 - v) + corner(blade + 1.0) * v) * su;
+        }
+
+        // Shirley and Chiu's concentric mapping keeps the samples stratified
+        let a = 2.0 * u - 1.0;
+        let b = 2.0 * v - 1.0;
+        if a == 0.0 && b == 0.0 { return Vector2::zeros(); }
+        let (radius, angle) = if a.abs() > b.abs() {
+            (a, pi / 4.0 * (b / a))
+        } else {
+            (b, pi / 2.0 - pi / 4.0 * (a / b))
+        };
+        return Vector2::new(angle.cos(), angle.sin()) * radius;
     }
 
-    fn translate_y(self: &mut Self, dist: f64) {
-        let up = self.up;
-        self.origin = self.origin + up * dist;
+    // Focuses on the surface seen through the pinhole at the window position and returns whether
+    // the focus changed
+    pub fn focus_on(self: &mut Self, scene: &Scene, screen: &Screen, x: Float, y: Float) -> bool {
+        let mut u = x / screen.width as Float;
+        let mut v = y / screen.height as Float;
+        // Focuses through the centre between the eyes
+        if let Some(stereo) = &self.stereo {
+            let (_, eye_u, eye_v) = stereo.split(u, v);
+            u = eye_u;
+            v = eye_v;
+        }
+        let (origin, direction) = match self.projection.generate(self, screen, u, v) {
+            None => { return false; }
+            Some(ray) => ray,
+        };
+
+        let mut ray = Ray::new();
+        ray.origin = origin;
+        ray.direction = direction;
+
+        let distance = match scene.intersect(&ray) {
+            None => { return false; }
+            Some((_, distance)) => distance,
+        };
+        let (_, _, forward) = self.axes();
+        let focus_distance = distance * ray.direction.dot(&forward);
+
+        let changed = (focus_distance - self.focus_distance).abs() > 1e-4;
+        self.focus_distance = focus_distance;
+        return changed;
     }
 
-    fn translate_z(self: &mut Self, dist: f64) {
+    // Applies the keyboard and mouse input over the frame's duration in seconds and returns whether
+    // the view changed, along with the result of saving the bookmarks if a view was bookmarked
+    pub fn update_input(self: &mut Self, window: &Window, controls: &mut Controls, frame_time: Float) -> (bool, Option<std::io::Result<()>>) {
+        let previous = (self.origin, self.forward, self.up, self.fov);
+        let keys = &controls.key_map;
+        let down = |key: Key| -> Float { if window.is_key_down(key) { 1.0 } else { 0.0 } };
+
+        let mut saved = None;
+        if window.is_key_pressed(keys.save_bookmark, KeyRepeat::No) {
+            controls.bookmarks.add_bookmark(self.pose());
+            saved = Some(controls.bookmarks.save(&controls.bookmark_file));
+        }
+        if window.is_key_pressed(keys.next_bookmark, KeyRepeat::No) && !controls.bookmarks.keyframes.is_empty() {
+            let next = controls.current_bookmark.map_or(0, |index| (index + 1) % controls.bookmarks.keyframes.len());
+            controls.current_bookmark = Some(next);
+            let pose = controls.bookmarks.keyframes[next].pose;
+            self.set_pose(&pose);
+            controls.orbit_pivot = None;
+        }
+
+        if window.is_key_pressed(keys.toggle_orbit, KeyRepeat::No) {
+            controls.orbit_pivot = match controls.orbit_pivot {
+                None => Some(self.origin + self.forward.normalize() * self.focus_distance),
+                Some(_) => None,
+            };
+        }
+
+        // Speeds are per second, so the camera moves as fast at any frame rate
+        let sprint = if window.is_key_down(keys.sprint) { 2.0 } else { 1.0 };
+        let distance = controls.move_speed * sprint * frame_time;
+        let angle = controls.turn_speed * sprint * frame_time;
+
+        // Translation
+        let right: Vector3<Float> = self.up.cross(&self.forward);
+        let up = self.up;
         let forward = self.forward;
-        self.origin = self.origin + forward * dist;
-    }
+        let sideways = right * (down(keys.move_right) - down(keys.move_left)) + up * (down(keys.move_up) - down(keys.move_down));
+        self.origin += sideways * distance;
+        match &mut controls.orbit_pivot {
+            None => { self.origin += forward * (down(keys.move_forward) - down(keys.move_backward)) * distance; }
+            Some(pivot) => {
+                // Panning takes the pivot along, moving forward closes in on it without passing it
+                *pivot += sideways * distance;
+                let pivot_distance = (*pivot - self.origin).magnitude();
+                let dolly = (down(keys.move_forward) - down(keys.move_backward)) * distance;
+                self.origin += forward * dolly.min(pivot_distance - 0.1);
+            }
+        }
 
-    fn rotate_x(self: &mut Self, angle: f64) {
-        let rotation_matrix = Matrix4::from_scaled_axis(Vector3::x() * angle);
-        self.up = rotation_matrix.transform_vector(&self.up);
-        self.forward = rotation_matrix.transform_vector(&self.forward);
-    }
+        // Rotation about the camera's own axes, from the keys and from dragging with the left button
+        let mut pitch = (down(keys.look_down) - down(keys.look_up)) * angle;
+        let mut yaw = (down(keys.look_right) - down(keys.look_left)) * angle;
+        let roll = (down(keys.roll_right) - down(keys.roll_left)) * angle;
+
+        let mouse = window.get_mouse_pos(MouseMode::Pass).filter(|_| window.get_mouse_down(MouseButton::Left));
+        if let (Some((x, y)), Some((last_x, last_y))) = (mouse, controls.last_mouse) {
+            yaw += (x - last_x) as Float * controls.mouse_sensitivity;
+            pitch += (y - last_y) as Float * controls.mouse_sensitivity;
+        }
+        controls.last_mouse = mouse;
+
+        self.rotate(&right, pitch, controls.orbit_pivot);
+        self.rotate(&up, yaw, controls.orbit_pivot);
+        self.rotate(&forward, roll, controls.orbit_pivot);
 
-    fn rotate_y(self: &mut Self, angle: f64) {
-        let rotation_matrix = Matrix4::from_scaled_axis(Vector3::y() * angle);
-        self.up = rotation_matrix.transform_vector(&self.up);
-        self.forward = rotation_matrix.transform_vector(&self.forward);
+        // Scrolling zooms in by narrowing the view. Planar projections cannot reach 180°, a fisheye
+        // stops at the whole sphere and panoramas always show everything.
+        let mut zoomed = false;
+        if let Some((_, scroll)) = window.get_scroll_wheel().filter(|(_, scroll)| *scroll != 0.0) {
+            let step = scroll.signum() as Float;
+            match &mut self.projection {
+                Projection::Perspective => { self.fov = (self.fov - step * 5.0).max(5.0).min(175.0); }
+                Projection::Fisheye { .. } => { self.fov = (self.fov - step * 5.0).max(5.0).min(360.0); }
+                Projection::Orthographic { height } => {
+                    *height *= if step > 0.0 { 0.9 } else { 1.0 / 0.9 };
+                    zoomed = true;
+                }
+                Projection::Equirectangular | Projection::Cubemap => {}
+            }
+        }
+
+        return (zoomed || previous != (self.origin, self.forward, self.up, self.fov), saved);
     }
 
-    fn rotate_z(self: &mut Self, angle: f64) {
-        let rotation_matrix = Matrix4::from_scaled_axis(Vector3::z() * angle);
-        self.up = rotation_matrix.transform_vector(&self.up);
-        self.forward = rotation_matrix.transform_vector(&self.forward);
+    // Rotates the view about the axis through the camera, or through the pivot while orbiting so
+    // the camera keeps facing it
+    fn rotate(self: &mut Self, axis: &Vector3<Float>, angle: Float, pivot: Option<Vector3<Float>>) {
+        if angle == 0.0 { return; }
+        let rotation = Rotation3::from_axis_angle(&Unit::new_normalize(*axis), angle);
+        if let Some(pivot) = pivot {
+            self.origin = pivot + rotation * (self.origin - pivot);
+        }
+        self.up = rotation * self.up;
+        self.forward = rotation * self.forward;
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,283 @@
+use std::{fs, io::{Error, ErrorKind, Result}};
+
+use nalgebra::{UnitQuaternion, Vector3};
+
+use crate::Float;
+
+use super::Camera;
+
+// Where a camera is and where it looks
+#[derive(Clone, Copy, Debug)]
+pub struct CameraPose {
+    pub origin: Vector3<Float>,
+    pub forward: Vector3<Float>,
+    pub up: Vector3<Float>,
+    pub fov: Float,
+}
+
+impl CameraPose {
+    fn orientation(self: &Self) -> UnitQuaternion<Float> {
+        return UnitQuaternion::face_towards(&self.forward, &self.up);
+    }
+}
+
+impl Camera {
+    pub fn pose(self: &Self) -> CameraPose {
+        return CameraPose {
+            origin: self.origin,
+            forward: self.forward,
+            up: self.up,
+            fov: self.fov,
+        };
+    }
+
+    pub fn set_pose(self: &mut Self, pose: &CameraPose) {
+        self.origin = pose.origin;
+        self.forward = pose.forward;
+        self.up = pose.up;
+        self.fov = pose.fov;
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    pub time: Float,
+    pub pose: CameraPose,
+}
+
+// Poses at moments in time, which the camera passes through smoothly. Saved bookmarks form a path
+// too, with each bookmark one second after the previous one.
+//
+// A path file holds one keyframe per line, as its time followed by the origin, forward and up
+// vectors and the field of view, all separated by spaces. A `#` starts a comment.
+pub struct CameraPath {
+    // Sorted by time
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath {
+    pub fn load(path: &str) -> Result<CameraPath> {
+        return CameraPath::parse(&fs::read_to_string(path)?);
+    }
+
+    pub fn parse(contents: &str) -> Result<CameraPath> {
+        let mut keyframes = Vec::new();
+
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() { continue; }
+
+            let values: Vec<Float> = line.split_whitespace()
+                .map(|value| value.parse())
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|_| Error::new(ErrorKind::InvalidData, format!("Expected numbers in `{}`", line)))?;
+            if values.len() != 11 {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Expected 11 numbers in `{}`", line)));
+            }
+            if values.iter().any(|value| !value.is_finite()) {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Expected finite numbers in `{}`", line)));
+            }
+
+            keyframes.push(Keyframe {
+                time: values[0],
+                pose: CameraPose {
+                    origin: Vector3::new(values[1], values[2], values[3]),
+                    forward: Vector3::new(values[4], values[5], values[6]),
+                    up: Vector3::new(values[7], values[8], values[9]),
+                    fov: values[10],
+                },
+            });
+        }
+
+        if keyframes.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "Expected at least one keyframe"));
+        }
+
+        keyframes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+        return Ok(CameraPath { keyframes });
+    }
+
+    pub fn save(self: &Self, path: &str) -> Result<()> {
+        let mut contents = String::from("# time origin forward up fov\n");
+        for keyframe in &self.keyframes {
+            let pose = &keyframe.pose;
+            contents += &format!(
+                "{} {} {} {} {} {} {} {} {} {} {}\n",
+                keyframe.time,
+                pose.origin.x, pose.origin.y, pose.origin.z,
+                pose.forward.x, pose.forward.y, pose.forward.z,
+                pose.up.x, pose.up.y, pose.up.z,
+                pose.fov,
+            );
+        }
+        return fs::write(path, contents);
+    }
+
+    // Appends the pose one second after the last keyframe
+    pub fn add_bookmark(self: &mut Self, pose: CameraPose) {
+        let time = self.keyframes.last().map_or(0.0, |keyframe| keyframe.time + 1.0);
+        self.keyframes.push(Keyframe { time, pose });
+    }
+
+    pub fn start_time(self: &Self) -> Float {
+        return self.keyframes.first().expect("The camera path has no keyframes").time;
+    }
+
+    pub fn end_time(self: &Self) -> Float {
+        return self.keyframes.last().expect("The camera path has no keyframes").time;
+    }
+
+    // Pose at the time, following a Catmull-Rom spline through the keyframe positions and turning
+    // along the shortest arc between their orientations
+    pub fn evaluate(self: &Self, time: Float) -> CameraPose {
+        let keyframes = &self.keyframes;
+        let last = keyframes.len().checked_sub(1).expect("The camera path has no keyframes");
+        if last == 0 || time <= keyframes[0].time { return keyframes[0].pose; }
+        if time >= keyframes[last].time { return keyframes[last].pose; }
+
+        // Segment from keyframe i to i + 1 containing the time
+        let i = keyframes.iter().rposition(|keyframe| keyframe.time <= time).unwrap();
+        let (k0, k1) = (&keyframes[i], &keyframes[i + 1]);
+        let duration = k1.time - k0.time;
+        let s = if duration > 0.0 { (time - k0.time) / duration } else { 0.0 };
+
+        // Tangents from the neighbouring keyframes, scaled to this segment's duration since the
+        // keyframes need not be evenly spaced in time
+        let tangent = |index: usize| -> Vector3<Float> {
+            let before = &keyframes[index.saturating_sub(1)];
+            let after = &keyframes[(index + 1).min(last)];
+            let span = after.time - before.time;
+            if span <= 0.0 { return Vector3::zeros(); }
+            return (after.pose.origin - before.pose.origin) * (duration / span);
+        };
+
+        // Cubic Hermite basis
+        let s2 = s * s;
+        let s3 = s2 * s;
+        let origin = k0.pose.origin * (2.0 * s3 - 3.0 * s2 + 1.0)
+            + tangent(i) * (s3 - 2.0 * s2 + s)
+            + k1.pose.origin * (-2.0 * s3 + 3.0 * s2)
+            + tangent(i + 1) * (s3 - s2);
+
+        let orientation = slerp_shortest(&k0.pose.orientation(), &k1.pose.orientation(), s);
+
+        return CameraPose {
+            origin,
+            forward: orientation * Vector3::z(),
+            up: orientation * Vector3::y(),
+            fov: k0.pose.fov + (k1.pose.fov - k0.pose.fov) * s,
+        };
+    }
+}
+
+// Slerp along the shorter of the two arcs, as q and -q are the same orientation
+fn slerp_shortest(q0: &UnitQuaternion<Float>, q1: &UnitQuaternion<Float>, s: Float) -> UnitQuaternion<Float> {
+    let mut q1 = *q1;
+    if q0.coords.dot(&q1.coords) < 0.0 {
+        q1 = UnitQuaternion::new_unchecked(-q1.into_inner());
+    }
+    return q0.try_slerp(&q1, s, 1e-6).unwrap_or(q1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: Float = 1e-4;
+
+    fn pose(x: Float, yaw: Float, fov: Float) -> CameraPose {
+        return CameraPose {
+            origin: Vector3::new(x, 1.0, -2.0),
+            forward: Vector3::new(yaw.sin(), 0.0, yaw.cos()),
+            up: Vector3::y(),
+            fov,
+        };
+    }
+
+    fn path(keyframes: &[(Float, CameraPose)]) -> CameraPath {
+        return CameraPath {
+            keyframes: keyframes.iter().map(|&(time, pose)| Keyframe { time, pose }).collect(),
+        };
+    }
+
+    fn assert_close(a: &Vector3<Float>, b: &Vector3<Float>) {
+        assert!((a - b).norm() < EPSILON, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn evaluate_hits_every_keyframe() {
+        let path = path(&[(0.0, pose(0.0, 0.0, 60.0)), (0.5, pose(2.0, 0.4, 70.0)), (2.0, pose(-1.0, 1.2, 90.0)), (5.0, pose(3.0, 2.5, 45.0))]);
+        for keyframe in &path.keyframes {
+            let pose = path.evaluate(keyframe.time);
+            assert_close(&pose.origin, &keyframe.pose.origin);
+            assert_close(&pose.forward, &keyframe.pose.forward);
+            assert_close(&pose.up, &keyframe.pose.up);
+            assert!((pose.fov - keyframe.pose.fov).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn evaluate_clamps_outside_the_path() {
+        let path = path(&[(1.0, pose(0.0, 0.0, 60.0)), (2.0, pose(4.0, 1.0, 80.0))]);
+        assert_close(&path.evaluate(-3.0).origin, &Vector3::new(0.0, 1.0, -2.0));
+        assert_close(&path.evaluate(10.0).origin, &Vector3::new(4.0, 1.0, -2.0));
+        assert_eq!(path.evaluate(10.0).fov, 80.0);
+    }
+
+    #[test]
+    fn evaluate_scales_tangents_to_uneven_keyframe_spacing() {
+        // Positions moving at a constant speed stay on a straight line at that speed, however far
+        // apart in time the keyframes are
+        let path = path(&[(0.0, pose(0.0, 0.0, 60.0)), (1.0, pose(1.0, 0.0, 60.0)), (3.0, pose(3.0, 0.0, 60.0))]);
+        for &time in &[0.25, 0.5, 1.5, 2.0, 2.75] {
+            assert_close(&path.evaluate(time).origin, &Vector3::new(time, 1.0, -2.0));
+        }
+    }
+
+    #[test]
+    fn slerp_ignores_the_quaternion_sign() {
+        let q0 = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.2);
+        let q1 = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.7);
+        let negated = UnitQuaternion::new_unchecked(-q1.into_inner());
+        for &s in &[0.0, 0.3, 0.5, 1.0] {
+            assert!(slerp_shortest(&q0, &q1, s).angle_to(&slerp_shortest(&q0, &negated, s)) < EPSILON);
+        }
+        let halfway = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.45);
+        assert!(slerp_shortest(&q0, &negated, 0.5).angle_to(&halfway) < EPSILON);
+    }
+
+    #[test]
+    fn load_reads_back_what_save_writes() {
+        let path = path(&[(0.0, pose(0.1, 0.3, 60.0)), (1.0 / 3.0, pose(-2.7, 1.1, 72.5)), (4.0, pose(1e-7, -0.9, 100.0))]);
+        let file = std::env::temp_dir().join(format!("camerapath-{}.txt", std::process::id()));
+        let file = file.to_str().unwrap();
+        path.save(file).unwrap();
+        let loaded = CameraPath::load(file).unwrap();
+        fs::remove_file(file).unwrap();
+
+        assert_eq!(loaded.keyframes.len(), path.keyframes.len());
+        for (a, b) in loaded.keyframes.iter().zip(&path.keyframes) {
+            assert_eq!(a.time, b.time);
+            assert_eq!(a.pose.origin, b.pose.origin);
+            assert_eq!(a.pose.forward, b.pose.forward);
+            assert_eq!(a.pose.up, b.pose.up);
+            assert_eq!(a.pose.fov, b.pose.fov);
+        }
+    }
+
+    #[test]
+    fn parse_skips_comments_and_sorts_by_time() {
+        let path = CameraPath::parse("# time origin forward up fov\n\n2 1 0 0 0 0 1 0 1 0 90 # end\n  0 0 0 0 0 0 1 0 1 0 60\n").unwrap();
+        let times: Vec<Float> = path.keyframes.iter().map(|keyframe| keyframe.time).collect();
+        assert_eq!(times, vec![0.0, 2.0]);
+        assert_eq!(path.keyframes[1].pose.fov, 90.0);
+    }
+
+    #[test]
+    fn parse_rejects_malformed_lines() {
+        for line in &["0 0 0 0 0 0 1 0 1 0", "0 0 0 0 0 0 1 0 1 0 60 1", "0 0 0 zero 0 0 1 0 1 0 60", "NaN 0 0 0 0 0 1 0 1 0 60", "0 0 inf 0 0 0 1 0 1 0 60", "", "# time origin forward up fov\n\n"] {
+            let error = CameraPath::parse(line).err().expect(line);
+            assert_eq!(error.kind(), ErrorKind::InvalidData);
+        }
+    }
+}
@@ -2,6 +2,8 @@ use std::ops::{Add, Mul, AddAssign};
 
 use nalgebra::clamp;
 
+use super::{WAVELENGTH_MAX, WAVELENGTH_MIN, cie_xyz, planck, xyz_to_color};
+
 #[derive(Clone, Copy, Debug)]
 pub struct Color {
     pub red: f64,
@@ -17,6 +19,34 @@ impl Color {
         return (r << 16) + (g << 8) + b;
     }
     
+    // Relative luminance of linear sRGB
+    pub fn luminance(self: &Self) -> f64 {
+        return 0.2126 * self.red + 0.7152 * self.green + 0.0722 * self.blue;
+    }
+
+    // Colour of a black body at the given temperature in Kelvin, scaled to a luminance of one so it
+    // can be multiplied by the light's power
+    pub fn from_temperature(kelvin: f64) -> Color {
+        let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+        let mut wavelength = WAVELENGTH_MIN;
+        while wavelength <= WAVELENGTH_MAX {
+            let radiance = planck(wavelength, kelvin);
+            let (cx, cy, cz) = cie_xyz(wavelength);
+            x += radiance * cx;
+            y += radiance * cy;
+            z += radiance * cz;
+            wavelength += 5.0;
+        }
+
+        let color = xyz_to_color(x / y, 1.0, z / y);
+        let clamped = Color {
+            red: color.red.max(0.0),
+            green: color.green.max(0.0),
+            blue: color.blue.max(0.0),
+        };
+        return clamped * (1.0 / clamped.luminance());
+    }
+
     pub fn black() -> Color {
         return Color {
             red: 0.0,
@@ -25,6 +55,14 @@ impl Color {
         };
     }
 
+    pub fn white() -> Color {
+        return Color {
+            red: 1.0,
+            green: 1.0,
+            blue: 1.0,
+        };
+    }
+
     pub fn red() -> Color {
         return Color {
             red: 1.0,
@@ -62,6 +100,18 @@ impl Mul<f64> for Color {
     }
 }
 
+impl Mul<Color> for Color {
+    type Output = Color;
+
+    fn mul(self, other: Color) -> Color {
+        Color {
+            red: self.red * other.red,
+            green: self.green * other.green,
+            blue: self.blue * other.blue,
+        }
+    }
+}
+
 impl Add for Color {
     type Output = Color;
 
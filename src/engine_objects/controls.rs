@@ -0,0 +1,173 @@
+use std::{fs, io::{Error, ErrorKind, Result}};
+
+use minifb::Key;
+use nalgebra::Vector3;
+
+use crate::Float;
+
+use super::CameraPath;
+
+// Keys bound to each camera action. A key map file holds one `action = Key` binding per line, with
+// `#` starting a comment, and changes only the actions it names.
+pub struct KeyMap {
+    pub move_right: Key,
+    pub move_left: Key,
+    pub move_up: Key,
+    pub move_down: Key,
+    pub move_forward: Key,
+    pub move_backward: Key,
+    pub look_up: Key,
+    pub look_down: Key,
+    pub look_right: Key,
+    pub look_left: Key,
+    pub roll_right: Key,
+    pub roll_left: Key,
+    pub sprint: Key,
+    // Switches between flying around and orbiting the point in focus
+    pub toggle_orbit: Key,
+    // Adds the current view to the bookmarks file, and jumps to the next saved view
+    pub save_bookmark: Key,
+    pub next_bookmark: Key,
+}
+
+impl KeyMap {
+    pub fn default() -> KeyMap {
+        return KeyMap {
+            move_right: Key::D,
+            move_left: Key::A,
+            move_up: Key::R,
+            move_down: Key::F,
+            move_forward: Key::W,
+            move_backward: Key::S,
+            look_up: Key::Up,
+            look_down: Key::Down,
+            look_right: Key::Right,
+            look_left: Key::Left,
+            roll_right: Key::E,
+            roll_left: Key::Q,
+            sprint: Key::LeftShift,
+            toggle_orbit: Key::O,
+            save_bookmark: Key::B,
+            next_bookmark: Key::N,
+        };
+    }
+
+    pub fn load(path: &str) -> Result<KeyMap> {
+        return KeyMap::parse(&fs::read_to_string(path)?);
+    }
+
+    pub fn parse(contents: &str) -> Result<KeyMap> {
+        let mut key_map = KeyMap::default();
+
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() { continue; }
+
+            let invalid = |message: String| Error::new(ErrorKind::InvalidData, message);
+            let mut parts = line.splitn(2, '=');
+            let action = parts.next().unwrap_or("").trim();
+            let name = parts.next().ok_or_else(|| invalid(format!("Expected `action = Key` in `{}`", line)))?.trim();
+            let key = key_from_name(name).ok_or_else(|| invalid(format!("Unknown key `{}`", name)))?;
+
+            let binding = match action {
+                "move_right" => &mut key_map.move_right,
+                "move_left" => &mut key_map.move_left,
+                "move_up" => &mut key_map.move_up,
+                "move_down" => &mut key_map.move_down,
+                "move_forward" => &mut key_map.move_forward,
+                "move_backward" => &mut key_map.move_backward,
+                "look_up" => &mut key_map.look_up,
+                "look_down" => &mut key_map.look_down,
+                "look_right" => &mut key_map.look_right,
+                "look_left" => &mut key_map.look_left,
+                "roll_right" => &mut key_map.roll_right,
+                "roll_left" => &mut key_map.roll_left,
+                "sprint" => &mut key_map.sprint,
+                "toggle_orbit" => &mut key_map.toggle_orbit,
+                "save_bookmark" => &mut key_map.save_bookmark,
+                "next_bookmark" => &mut key_map.next_bookmark,
+                _ => { return Err(invalid(format!("Unknown action `{}`", action))); }
+            };
+            *binding = key;
+        }
+        return Ok(key_map);
+    }
+}
+
+// Looks a key up by the name of its variant, ignoring case, so `W`, `Space` and `LeftShift` work
+fn key_from_name(name: &str) -> Option<Key> {
+    let keys = [
+        Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+        Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+        Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+        Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+        Key::Down, Key::Left, Key::Right, Key::Up,
+        Key::Apostrophe, Key::Backquote, Key::Backslash, Key::Comma, Key::Equal, Key::LeftBracket, Key::Minus,
+        Key::Period, Key::RightBracket, Key::Semicolon, Key::Slash,
+        Key::Backspace, Key::Delete, Key::End, Key::Enter, Key::Home, Key::Insert, Key::PageDown, Key::PageUp,
+        Key::Space, Key::Tab, Key::LeftShift, Key::RightShift, Key::LeftCtrl, Key::RightCtrl, Key::LeftAlt, Key::RightAlt,
+        Key::NumPad0, Key::NumPad1, Key::NumPad2, Key::NumPad3, Key::NumPad4, Key::NumPad5, Key::NumPad6,
+        Key::NumPad7, Key::NumPad8, Key::NumPad9, Key::NumPadDot, Key::NumPadSlash, Key::NumPadAsterisk,
+        Key::NumPadMinus, Key::NumPadPlus, Key::NumPadEnter,
+    ];
+    return keys.iter().copied().find(|key| format!("{:?}", key).eq_ignore_ascii_case(name));
+}
+
+// State of the interactive viewer's camera controls
+pub struct Controls {
+    pub key_map: KeyMap,
+    // Units per second
+    pub move_speed: Float,
+    // Radians per second
+    pub turn_speed: Float,
+    // Radians per pixel the mouse is dragged
+    pub mouse_sensitivity: Float,
+    // Point the camera circles around while orbiting
+    pub orbit_pivot: Option<Vector3<Float>>,
+    // Mouse position during a drag, to turn by how far it moved since the last frame
+    pub last_mouse: Option<(f32, f32)>,
+    // Saved views and the file they are kept in
+    pub bookmarks: CameraPath,
+    pub bookmark_file: String,
+    // Bookmark the camera jumped to last
+    pub current_bookmark: Option<usize>,
+}
+
+impl Controls {
+    pub fn new(key_map: KeyMap, bookmarks: CameraPath, bookmark_file: String) -> Controls {
+        return Controls {
+            key_map,
+            move_speed: 2.0,
+            turn_speed: 1.0,
+            mouse_sensitivity: 0.005,
+            orbit_pivot: None,
+            last_mouse: None,
+            bookmarks,
+            bookmark_file,
+            current_bookmark: None,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_changes_only_the_named_actions() {
+        let key_map = KeyMap::parse("# Arrow keys to move\nmove_forward = up\n  move_backward=DOWN  \n\nsprint = Space # run\n").unwrap();
+        assert_eq!(key_map.move_forward, Key::Up);
+        assert_eq!(key_map.move_backward, Key::Down);
+        assert_eq!(key_map.sprint, Key::Space);
+        assert_eq!(key_map.move_left, Key::A);
+        assert_eq!(key_map.look_up, Key::Up);
+    }
+
+    #[test]
+    fn parse_rejects_malformed_lines() {
+        for line in &["jump = Space", "sprint = Hyper", "sprint Space", "= W"] {
+            let error = KeyMap::parse(line).err().expect(line);
+            assert_eq!(error.kind(), ErrorKind::InvalidData);
+        }
+    }
+}
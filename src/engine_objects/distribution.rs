@@ -0,0 +1,139 @@
+use crate::Float;
+
+// Piecewise-constant distribution for sampling indices proportionally to their weight
+pub struct Distribution1D {
+    pub function: Vec<f64>,
+    pub cdf: Vec<f64>,
+    pub integral: f64,
+}
+
+impl Distribution1D {
+    pub fn new(function: Vec<f64>) -> Distribution1D {
+        let count = function.len();
+        let mut cdf = vec![0.0; count + 1];
+        for i in 0..count {
+            cdf[i + 1] = cdf[i] + function[i].max(0.0) / count as f64;
+        }
+
+        let integral = cdf[count];
+        for i in 1..=count {
+            // Fall back to a uniform distribution when every weight is zero
+            cdf[i] = if integral > 0.0 { cdf[i] / integral } else { i as f64 / count as f64 };
+        }
+
+        return Distribution1D {
+            function,
+            cdf,
+            integral,
+        };
+    }
+
+    pub fn count(self: &Self) -> usize {
+        return self.function.len();
+    }
+
+    // Picks an index for u in [0, 1), returning it with its discrete probability and u remapped
+    // to [0, 1) within the chosen segment so it can be reused for further sampling
+    pub fn sample_discrete(self: &Self, u: Float) -> (usize, f64, Float) {
+        let u = u as f64;
+        let index = self.find_segment(u);
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let remapped = if width > 0.0 { (u - self.cdf[index]) / width } else { 0.0 };
+        return (index, width, (remapped as Float).min(1.0 - Float::EPSILON));
+    }
+
+    // Samples a continuous value in [0, 1), returning it with its density and the chosen segment
+    pub fn sample_continuous(self: &Self, u: Float) -> (Float, f64, usize) {
+        let (index, width, remapped) = self.sample_discrete(u);
+        let pdf = width * self.count() as f64;
+        return ((index as Float + remapped) / self.count() as Float, pdf, index);
+    }
+
+    fn find_segment(self: &Self, u: f64) -> usize {
+        // Largest index whose cdf value is at most u
+        let mut low = 0;
+        let mut high = self.count();
+        while low + 1 < high {
+            let mid = (low + high) / 2;
+            if self.cdf[mid] <= u { low = mid; } else { high = mid; }
+        }
+        return low;
+    }
+}
+
+
+// Distribution over a grid of weights, sampled by picking a row from the marginal distribution and
+// then a column from that row's conditional distribution
+pub struct Distribution2D {
+    pub conditionals: Vec<Distribution1D>,
+    pub marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(function: &Vec<f64>, width: usize, height: usize) -> Distribution2D {
+        let conditionals: Vec<Distribution1D> = (0..height)
+            .map(|y| Distribution1D::new(function[(y * width)..((y + 1) * width)].to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(conditionals.iter().map(|c| c.integral).collect());
+
+        return Distribution2D {
+            conditionals,
+            marginal,
+        };
+    }
+
+    // Returns a point in [0, 1)² with its density
+    pub fn sample_continuous(self: &Self, u: Float, v: Float) -> ((Float, Float), f64) {
+        let (y, marginal_pdf, row) = self.marginal.sample_continuous(v);
+        let (x, conditional_pdf, _) = self.conditionals[row].sample_continuous(u);
+        return ((x, y), marginal_pdf * conditional_pdf);
+    }
+}
+
+// Walker's alias method, samples an index in constant time regardless of the number of weights
+pub struct AliasTable {
+    pub probabilities: Vec<f64>,
+    pub aliases: Vec<usize>,
+    pub pdfs: Vec<f64>,
+}
+
+impl AliasTable {
+    pub fn new(weights: &Vec<f64>) -> AliasTable {
+        let count = weights.len();
+        let total: f64 = weights.iter().map(|w| w.max(0.0)).sum();
+        let pdfs: Vec<f64> = weights.iter()
+            .map(|w| if total > 0.0 { w.max(0.0) / total } else { 1.0 / count as f64 })
+            .collect();
+
+        // Split the scaled probabilities into under- and overfull buckets and pair them up
+        let mut scaled: Vec<f64> = pdfs.iter().map(|p| p * count as f64).collect();
+        let mut probabilities = vec![1.0; count];
+        let mut aliases: Vec<usize> = (0..count).collect();
+        let mut small: Vec<usize> = (0..count).filter(|&i| scaled[i] < 1.0).collect();
+        let mut large: Vec<usize> = (0..count).filter(|&i| scaled[i] >= 1.0).collect();
+
+        while let (Some(less), Some(more)) = (small.pop(), large.pop()) {
+            probabilities[less] = scaled[less];
+            aliases[less] = more;
+            scaled[more] = scaled[more] + scaled[less] - 1.0;
+            if scaled[more] < 1.0 { small.push(more); } else { large.push(more); }
+        }
+
+        return AliasTable {
+            probabilities,
+            aliases,
+            pdfs,
+        };
+    }
+
+    // Returns the sampled index with its probability
+    pub fn sample(self: &Self, u: Float) -> (usize, f64) {
+        let count = self.probabilities.len();
+        let scaled = u as f64 * count as f64;
+        let bucket = (scaled as usize).min(count - 1);
+        let remainder = scaled - bucket as f64;
+
+        let index = if remainder < self.probabilities[bucket] { bucket } else { self.aliases[bucket] };
+        return (index, self.pdfs[index]);
+    }
+}
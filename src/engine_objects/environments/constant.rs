@@ -0,0 +1,31 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::{Color, lights::{Light, LightSample}}};
+
+use super::{Environment, environment_power, illuminate_uniform};
+
+// Environment of a single colour in every direction
+pub struct ConstantEnvironment {
+    pub color: Color,
+    pub samples: usize,
+}
+
+impl Environment for ConstantEnvironment {
+    fn radiance(self: &Self, _direction: &Unit<Vector3<Float>>) -> Color {
+        return self.color;
+    }
+}
+
+impl Light for ConstantEnvironment {
+    fn illuminate(self: &Self, _point: &Vector3<Float>, u: Float, v: Float) -> Option<LightSample> {
+        return illuminate_uniform(self, u, v);
+    }
+
+    fn sample_count(self: &Self) -> usize {
+        return self.samples;
+    }
+
+    fn power(self: &Self, scene_radius: Float) -> f64 {
+        return environment_power(self.color.luminance(), scene_radius);
+    }
+}
@@ -0,0 +1,36 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::{Color, lights::{Light, LightSample}}};
+
+// Light arriving from infinitely far away, seen by rays that leave the scene and sampled as a
+// light source for direct lighting
+pub trait Environment: Light {
+    fn radiance(self: &Self, direction: &Unit<Vector3<Float>>) -> Color;
+}
+
+// Uniformly samples a direction on the unit sphere
+pub fn uniform_sample_sphere(u: Float, v: Float) -> Unit<Vector3<Float>> {
+    let pi = std::f64::consts::PI as Float;
+    let y = 1.0 - 2.0 * u;
+    let r = (1.0 - y * y).max(0.0).sqrt();
+    let phi = 2.0 * pi * v;
+    return Unit::new_normalize(Vector3::new(r * phi.sin(), y, r * phi.cos()));
+}
+
+// Illuminates a point from a uniformly sampled direction, for environments without a better strategy
+pub fn illuminate_uniform(environment: &dyn Environment, u: Float, v: Float) -> Option<LightSample> {
+    let sphere_area = 4.0 * std::f64::consts::PI;
+    let direction = uniform_sample_sphere(u, v);
+
+    return Some(LightSample {
+        direction,
+        distance: Float::INFINITY,
+        energy: environment.radiance(&direction) * sphere_area,
+    });
+}
+
+// Power of an environment with the given average luminance reaching a scene of the given radius
+pub fn environment_power(average_luminance: f64, scene_radius: Float) -> f64 {
+    let pi = std::f64::consts::PI;
+    return 4.0 * pi * pi * (scene_radius * scene_radius) as f64 * average_luminance;
+}
@@ -0,0 +1,103 @@
+use std::io::Result;
+
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::{Color, Distribution2D, lights::{Light, LightSample}}};
+
+use super::{Environment, environment_power, load_hdr};
+
+// Environment from an equirectangular (latitude-longitude) HDR image, with +Y as the up axis. Shadow
+// rays are importance sampled towards the bright parts of the image.
+pub struct EquirectangularEnvironment {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+    // Scale from the image's values to radiance in W/(m²·sr)
+    pub intensity: f64,
+    pub distribution: Distribution2D,
+    pub samples: usize,
+}
+
+impl EquirectangularEnvironment {
+    pub fn load(path: &str, intensity: f64, samples: usize) -> Result<EquirectangularEnvironment> {
+        let (width, height, pixels) = load_hdr(path)?;
+
+        // Weigh texels by luminance and by the solid angle they cover, which shrinks towards the poles
+        let pi = std::f64::consts::PI;
+        let mut weights = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let sin_theta = (pi * (y as f64 + 0.5) / height as f64).sin();
+            for x in 0..width {
+                weights.push(pixels[x + y * width].luminance() * sin_theta);
+            }
+        }
+        let distribution = Distribution2D::new(&weights, width, height);
+
+        return Ok(EquirectangularEnvironment {
+            width,
+            height,
+            pixels,
+            intensity,
+            distribution,
+            samples,
+        });
+    }
+
+    fn direction_to_uv(direction: &Unit<Vector3<Float>>) -> (Float, Float) {
+        let pi = std::f64::consts::PI as Float;
+        let u = 0.5 + direction.x.atan2(direction.z) / (2.0 * pi);
+        let v = direction.y.max(-1.0).min(1.0).acos() / pi;
+        return (u, v);
+    }
+
+    fn uv_to_direction(u: Float, v: Float) -> Unit<Vector3<Float>> {
+        let pi = std::f64::consts::PI as Float;
+        let theta = v * pi;
+        let phi = (u - 0.5) * 2.0 * pi;
+        return Unit::new_normalize(Vector3::new(theta.sin() * phi.sin(), theta.cos(), theta.sin() * phi.cos()));
+    }
+
+    fn lookup(self: &Self, u: Float, v: Float) -> Color {
+        let x = ((u * self.width as Float) as usize).min(self.width - 1);
+        let y = ((v * self.height as Float) as usize).min(self.height - 1);
+        return self.pixels[x + y * self.width] * self.intensity;
+    }
+}
+
+impl Environment for EquirectangularEnvironment {
+    fn radiance(self: &Self, direction: &Unit<Vector3<Float>>) -> Color {
+        let (u, v) = EquirectangularEnvironment::direction_to_uv(direction);
+        return self.lookup(u, v);
+    }
+}
+
+impl Light for EquirectangularEnvironment {
+    fn illuminate(self: &Self, _point: &Vector3<Float>, u: Float, v: Float) -> Option<LightSample> {
+        let pi = std::f64::consts::PI;
+        let ((x, y), map_pdf) = self.distribution.sample_continuous(u, v);
+        if map_pdf <= 0.0 { return None; }
+
+        let sin_theta = (pi * y as f64).sin();
+        if sin_theta <= 0.0 { return None; }
+
+        // Convert the density over the image to a density over solid angle
+        let pdf = map_pdf / (2.0 * pi * pi * sin_theta);
+        let direction = EquirectangularEnvironment::uv_to_direction(x, y);
+
+        return Some(LightSample {
+            direction,
+            distance: Float::INFINITY,
+            energy: self.lookup(x, y) * (1.0 / pdf),
+        });
+    }
+
+    fn sample_count(self: &Self) -> usize {
+        return self.samples;
+    }
+
+    fn power(self: &Self, scene_radius: Float) -> f64 {
+        let total: f64 = self.pixels.iter().map(|pixel| pixel.luminance()).sum();
+        let average = total / self.pixels.len() as f64 * self.intensity;
+        return environment_power(average, scene_radius);
+    }
+}
@@ -0,0 +1,38 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::{Color, lights::{Light, LightSample}}};
+
+use super::{Environment, environment_power, illuminate_uniform};
+
+// Analytic sky that blends from the horizon colour to the zenith colour, with a flat ground colour
+// below the horizon
+pub struct GradientEnvironment {
+    pub zenith: Color,
+    pub horizon: Color,
+    pub ground: Color,
+    pub samples: usize,
+}
+
+impl Environment for GradientEnvironment {
+    fn radiance(self: &Self, direction: &Unit<Vector3<Float>>) -> Color {
+        if direction.y < 0.0 { return self.ground; }
+
+        let t = (direction.y as f64).sqrt();
+        return self.horizon * (1.0 - t) + self.zenith * t;
+    }
+}
+
+impl Light for GradientEnvironment {
+    fn illuminate(self: &Self, _point: &Vector3<Float>, u: Float, v: Float) -> Option<LightSample> {
+        return illuminate_uniform(self, u, v);
+    }
+
+    fn sample_count(self: &Self) -> usize {
+        return self.samples;
+    }
+
+    fn power(self: &Self, scene_radius: Float) -> f64 {
+        let sky = (self.zenith.luminance() + self.horizon.luminance()) / 2.0;
+        return environment_power((sky + self.ground.luminance()) / 2.0, scene_radius);
+    }
+}
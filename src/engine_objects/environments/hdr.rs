@@ -0,0 +1,112 @@
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Result};
+
+use crate::engine_objects::Color;
+
+// Reads a Radiance RGBE (.hdr) image, returning its width, height and pixels in scanline order
+pub fn load_hdr(path: &str) -> Result<(usize, usize, Vec<Color>)> {
+    let mut reader = BufReader::new(std::fs::File::open(path)?);
+
+    // The header is a list of lines terminated by an empty line, followed by the resolution
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if !line.starts_with("#?") {
+        return Err(Error::new(ErrorKind::InvalidData, "Not a Radiance HDR file"));
+    }
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "Missing HDR resolution"));
+        }
+        if line.trim().is_empty() { break; }
+        if line.starts_with("FORMAT=") && !line.contains("32-bit_rle_rgbe") {
+            return Err(Error::new(ErrorKind::InvalidData, "Only RGBE HDR images are supported"));
+        }
+    }
+
+    line.clear();
+    reader.read_line(&mut line)?;
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.len() != 4 || tokens[0] != "-Y" || tokens[2] != "+X" {
+        return Err(Error::new(ErrorKind::InvalidData, "Only -Y +X oriented HDR images are supported"));
+    }
+    let parse = |s: &str| s.parse::<usize>().map_err(|_| Error::new(ErrorKind::InvalidData, "Invalid HDR resolution"));
+    let height = parse(tokens[1])?;
+    let width = parse(tokens[3])?;
+
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+
+    let mut pixels = Vec::with_capacity(width * height);
+    let mut position = 0;
+    let mut scanline = vec![0u8; width * 4];
+    for _ in 0..height {
+        position = read_scanline(&data, position, &mut scanline, width)?;
+        for x in 0..width {
+            pixels.push(rgbe_to_color(&scanline[(x * 4)..(x * 4 + 4)]));
+        }
+    }
+
+    return Ok((width, height, pixels));
+}
+
+fn read_scanline(data: &[u8], mut position: usize, scanline: &mut Vec<u8>, width: usize) -> Result<usize> {
+    let eof = || Error::new(ErrorKind::UnexpectedEof, "HDR pixel data ended early");
+    let header = data.get(position..(position + 4)).ok_or_else(eof)?;
+
+    // Flat scanline, either an old style file or one too narrow to be run length encoded
+    let is_rle = header[0] == 2 && header[1] == 2 && (header[2] & 0x80) == 0 && width >= 8 && width < 32768;
+    if !is_rle {
+        let bytes = data.get(position..(position + width * 4)).ok_or_else(eof)?;
+        scanline.copy_from_slice(bytes);
+        return Ok(position + width * 4);
+    }
+
+    if ((header[2] as usize) << 8 | header[3] as usize) != width {
+        return Err(Error::new(ErrorKind::InvalidData, "HDR scanline width mismatch"));
+    }
+    position += 4;
+
+    // Each of the four channels is stored separately as runs and literal spans
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = *data.get(position).ok_or_else(eof)? as usize;
+            position += 1;
+            if count > 128 {
+                let run = count - 128;
+                let value = *data.get(position).ok_or_else(eof)?;
+                position += 1;
+                if run == 0 || x + run > width {
+                    return Err(Error::new(ErrorKind::InvalidData, "Invalid HDR run length"));
+                }
+                for _ in 0..run {
+                    scanline[x * 4 + channel] = value;
+                    x += 1;
+                }
+            } else {
+                if count == 0 || x + count > width {
+                    return Err(Error::new(ErrorKind::InvalidData, "Invalid HDR run length"));
+                }
+                let values = data.get(position..(position + count)).ok_or_else(eof)?;
+                for value in values {
+                    scanline[x * 4 + channel] = *value;
+                    x += 1;
+                }
+                position += count;
+            }
+        }
+    }
+
+    return Ok(position);
+}
+
+fn rgbe_to_color(rgbe: &[u8]) -> Color {
+    if rgbe[3] == 0 { return Color::black(); }
+
+    let scale = 2.0f64.powi(rgbe[3] as i32 - 136);
+    return Color {
+        red: rgbe[0] as f64 * scale,
+        green: rgbe[1] as f64 * scale,
+        blue: rgbe[2] as f64 * scale,
+    };
+}
@@ -0,0 +1,12 @@
+mod environment;
+pub use environment::*;
+mod constant;
+pub use constant::*;
+mod gradient;
+pub use gradient::*;
+mod hdr;
+pub use hdr::*;
+mod equirectangular;
+pub use equirectangular::*;
+mod preetham;
+pub use preetham::*;
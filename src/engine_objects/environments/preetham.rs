@@ -0,0 +1,147 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::{Color, lights::{DirectionalLight, Light, LightSample}, xyz_to_color}};
+
+use super::{Environment, environment_power, illuminate_uniform};
+
+// Analytic daylight sky from Preetham et al., "A Practical Analytic Model for Daylight" (1999).
+// Luminance and chromaticity follow the Perez distribution relative to the zenith, the ground
+// below the horizon reflects the zenith colour scaled by its albedo.
+pub struct PreethamSky {
+    // Direction towards the sun
+    pub sun_direction: Unit<Vector3<Float>>,
+    // Haziness of the atmosphere, from 2 for a clear sky up to about 10 for a hazy one
+    pub turbidity: f64,
+    pub ground_albedo: Color,
+    // Scale from the model's luminance in kcd/m² to radiance in W/(m²·sr)
+    pub intensity: f64,
+    pub samples: usize,
+    perez_luminance: [f64; 5],
+    perez_x: [f64; 5],
+    perez_y: [f64; 5],
+    zenith: (f64, f64, f64),
+    zenith_color: Color,
+}
+
+impl PreethamSky {
+    pub fn new(sun_direction: Unit<Vector3<Float>>, turbidity: f64, ground_albedo: Color, intensity: f64, samples: usize) -> PreethamSky {
+        let t = turbidity;
+        // The model is only defined up to the horizon
+        let theta_s = (sun_direction.y as f64).max(0.0).min(1.0).acos();
+
+        let perez_luminance = [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703];
+        let perez_x = [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452];
+        let perez_y = [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (std::f64::consts::PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let theta2 = theta_s * theta_s;
+        let theta3 = theta2 * theta_s;
+        let zenith_x = t * t * (0.00166 * theta3 - 0.00375 * theta2 + 0.00209 * theta_s)
+            + t * (-0.02903 * theta3 + 0.06377 * theta2 - 0.03202 * theta_s + 0.00394)
+            + (0.11693 * theta3 - 0.21196 * theta2 + 0.06052 * theta_s + 0.25886);
+        let zenith_y = t * t * (0.00275 * theta3 - 0.00610 * theta2 + 0.00317 * theta_s)
+            + t * (-0.04214 * theta3 + 0.08970 * theta2 - 0.04153 * theta_s + 0.00516)
+            + (0.15346 * theta3 - 0.26756 * theta2 + 0.06670 * theta_s + 0.26688);
+
+        let mut sky = PreethamSky {
+            sun_direction,
+            turbidity,
+            ground_albedo,
+            intensity,
+            samples,
+            perez_luminance,
+            perez_x,
+            perez_y,
+            zenith: (zenith_luminance, zenith_x, zenith_y),
+            zenith_color: Color::black(),
+        };
+        sky.zenith_color = sky.sky_radiance(&Unit::new_unchecked(Vector3::y()));
+        return sky;
+    }
+
+    // Sun matching the sky, coloured by the light lost to Rayleigh and aerosol scattering on its way
+    // through the atmosphere. Irradiance is that of the sun in W/m² before the atmosphere.
+    pub fn sun_light(self: &Self, irradiance: f64) -> DirectionalLight {
+        let theta_s = (self.sun_direction.y as f64).max(0.0).min(1.0).acos();
+        let theta_degrees = theta_s.to_degrees();
+
+        // Relative optical mass of the atmosphere along the sun's path
+        let air_mass = 1.0 / (theta_s.cos() + 0.15 * (93.885 - theta_degrees).max(0.0001).powf(-1.253));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+
+        // Transmittance at representative wavelengths for red, green and blue, in micrometres
+        let transmittance = |lambda: f64| -> f64 {
+            let rayleigh = (-0.008735 * lambda.powf(-4.08) * air_mass).exp();
+            let aerosol = (-beta * lambda.powf(-1.3) * air_mass).exp();
+            return rayleigh * aerosol;
+        };
+
+        return DirectionalLight {
+            direction: -self.sun_direction,
+            irradiance: Color {
+                red: transmittance(0.68),
+                green: transmittance(0.55),
+                blue: transmittance(0.44),
+            } * irradiance,
+        };
+    }
+
+    fn perez(coefficients: &[f64; 5], cos_theta: f64, gamma: f64) -> f64 {
+        let [a, b, c, d, e] = *coefficients;
+        return (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos() * gamma.cos());
+    }
+
+    fn sky_radiance(self: &Self, direction: &Unit<Vector3<Float>>) -> Color {
+        let cos_theta = (direction.y as f64).max(0.001);
+        let cos_gamma = (direction.dot(&self.sun_direction) as f64).max(-1.0).min(1.0);
+        let gamma = cos_gamma.acos();
+        let theta_s = (self.sun_direction.y as f64).max(0.0).min(1.0).acos();
+
+        let distribution = |coefficients: &[f64; 5]| -> f64 {
+            PreethamSky::perez(coefficients, cos_theta, gamma) / PreethamSky::perez(coefficients, 1.0, theta_s)
+        };
+
+        let (zenith_luminance, zenith_x, zenith_y) = self.zenith;
+        let luminance = zenith_luminance * distribution(&self.perez_luminance);
+        let x = zenith_x * distribution(&self.perez_x);
+        let y = zenith_y * distribution(&self.perez_y);
+
+        return xyy_to_color(x, y, luminance) * self.intensity;
+    }
+}
+
+// Converts CIE xyY to linear sRGB
+fn xyy_to_color(x: f64, y: f64, luminance: f64) -> Color {
+    if y <= 0.0 { return Color::black(); }
+
+    let color = xyz_to_color(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+    return Color {
+        red: color.red.max(0.0),
+        green: color.green.max(0.0),
+        blue: color.blue.max(0.0),
+    };
+}
+
+impl Environment for PreethamSky {
+    fn radiance(self: &Self, direction: &Unit<Vector3<Float>>) -> Color {
+        if direction.y < 0.0 { return self.ground_albedo * self.zenith_color; }
+        return self.sky_radiance(direction);
+    }
+}
+
+impl Light for PreethamSky {
+    fn illuminate(self: &Self, _point: &Vector3<Float>, u: Float, v: Float) -> Option<LightSample> {
+        return illuminate_uniform(self, u, v);
+    }
+
+    fn sample_count(self: &Self) -> usize {
+        return self.samples;
+    }
+
+    fn power(self: &Self, scene_radius: Float) -> f64 {
+        let sky = self.zenith_color.luminance();
+        return environment_power((sky + (self.ground_albedo * self.zenith_color).luminance()) / 2.0, scene_radius);
+    }
+}
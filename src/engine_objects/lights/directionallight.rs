@@ -0,0 +1,32 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::Color};
+
+use super::{Light, LightSample};
+
+// Light from an infinitely distant source such as the sun, it has no position and no falloff
+pub struct DirectionalLight {
+    // Direction in which the light travels
+    pub direction: Unit<Vector3<Float>>,
+    // Irradiance per colour channel in W/m² on a surface facing the light
+    pub irradiance: Color,
+}
+
+impl Light for DirectionalLight {
+    fn illuminate(self: &Self, _point: &Vector3<Float>, _u: Float, _v: Float) -> Option<LightSample> {
+        return Some(LightSample {
+            direction: -self.direction,
+            distance: Float::INFINITY,
+            energy: self.irradiance,
+        });
+    }
+
+    fn is_delta(self: &Self) -> bool {
+        return true;
+    }
+
+    fn power(self: &Self, scene_radius: Float) -> f64 {
+        let disk_area = std::f64::consts::PI * (scene_radius * scene_radius) as f64;
+        return disk_area * self.irradiance.luminance();
+    }
+}
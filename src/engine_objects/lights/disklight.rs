@@ -0,0 +1,74 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::{Color, Ray}};
+
+use super::{Light, LightSample, sample_area};
+
+// Circular area light emitting on the side its normal points to
+pub struct DiskLight {
+    pub center: Vector3<Float>,
+    pub normal: Unit<Vector3<Float>>,
+    pub radius: Float,
+    // Emitted radiance per colour channel in W/(m²·sr)
+    pub radiance: Color,
+    pub samples: usize,
+}
+
+impl DiskLight {
+    fn tangents(self: &Self) -> (Vector3<Float>, Vector3<Float>) {
+        let helper = if self.normal.x.abs() > 0.9 { Vector3::y() } else { Vector3::x() };
+        let tangent: Vector3<Float> = self.normal.cross(&helper).normalize();
+        let bitangent: Vector3<Float> = self.normal.cross(&tangent);
+        return (tangent, bitangent);
+    }
+}
+
+// Maps the unit square to the unit disk while keeping the strata of stratified samples intact
+fn concentric_sample_disk(u: Float, v: Float) -> (Float, Float) {
+    let pi = std::f64::consts::PI as Float;
+    let a = 2.0 * u - 1.0;
+    let b = 2.0 * v - 1.0;
+
+    if a == 0.0 && b == 0.0 { return (0.0, 0.0); }
+
+    let (radius, theta) = if a.abs() > b.abs() {
+        (a, pi / 4.0 * (b / a))
+    } else {
+        (b, pi / 2.0 - pi / 4.0 * (a / b))
+    };
+    return (radius * theta.cos(), radius * theta.sin());
+}
+
+impl Light for DiskLight {
+    fn illuminate(self: &Self, point: &Vector3<Float>, u: Float, v: Float) -> Option<LightSample> {
+        let pi = std::f64::consts::PI as Float;
+        let (tangent, bitangent) = self.tangents();
+        let (x, y) = concentric_sample_disk(u, v);
+        let light_point: Vector3<Float> = self.center + (tangent * x + bitangent * y) * self.radius;
+        let area = pi * self.radius * self.radius;
+        return sample_area(point, &light_point, &self.normal, area, self.radiance);
+    }
+
+    fn sample_count(self: &Self) -> usize {
+        return self.samples;
+    }
+
+    fn intersect(self: &Self, ray: &Ray) -> Option<(Float, Color)> {
+        let denom = ray.direction.dot(&self.normal);
+        if denom >= 0.0 { return None; }
+
+        let distance = (self.center - ray.origin).dot(&self.normal) / denom;
+        if distance <= 0.0 { return None; }
+
+        let local: Vector3<Float> = ray.get_intersection_point(distance) - self.center;
+        if local.magnitude_squared() > self.radius * self.radius { return None; }
+
+        return Some((distance, self.radiance));
+    }
+
+    fn power(self: &Self, _scene_radius: Float) -> f64 {
+        let pi = std::f64::consts::PI;
+        let area = pi * (self.radius * self.radius) as f64;
+        return pi * area * self.radiance.luminance();
+    }
+}
@@ -0,0 +1,49 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::{Color, Ray}};
+
+pub struct LightSample {
+    // Direction from the shaded point towards the light
+    pub direction: Unit<Vector3<Float>>,
+    // Distance to the light, infinite for lights without a position
+    pub distance: Float,
+    // Irradiance in W/m² arriving at the shaded point from the light, falloff and sample pdf
+    // already applied, before the cosine at the shaded point
+    pub energy: Color,
+}
+
+pub trait Light {
+    // Returns the light arriving at point from a position on the light chosen by (u, v) in [0, 1)²
+    fn illuminate(self: &Self, point: &Vector3<Float>, u: Float, v: Float) -> Option<LightSample>;
+
+    // Number of stratified samples per axis, lights with an area need more than one for soft shadows
+    fn sample_count(self: &Self) -> usize { return 1; }
+
+    // Estimate of the total emitted power, used to pick important lights. Lights infinitely far
+    // away only reach the scene through a disk of the given radius.
+    fn power(self: &Self, scene_radius: Float) -> f64;
+
+    // Lights without an area cannot be hit by a ray, so their reflections only come from sampling
+    fn is_delta(self: &Self) -> bool { return false; }
+
+    // Intersects the visible surface of the light, returning the distance and the emitted light
+    fn intersect(self: &Self, _ray: &Ray) -> Option<(Float, Color)> { return None; }
+}
+
+// Builds the sample for a point on a one-sided emitting surface, converting the area pdf to solid angle
+pub fn sample_area(point: &Vector3<Float>, light_point: &Vector3<Float>, light_normal: &Vector3<Float>, area: Float, emission: Color) -> Option<LightSample> {
+    let light_vector: Vector3<Float> = light_point - point;
+    let distance: Float = light_vector.magnitude();
+    let direction: Unit<Vector3<Float>> = Unit::new_normalize(light_vector);
+
+    let cos_light = -direction.dot(light_normal);
+    if cos_light <= 0.0 { return None; }
+
+    let geometry_term = (cos_light * area / (distance * distance)) as f64;
+
+    return Some(LightSample {
+        direction,
+        distance,
+        energy: emission * geometry_term,
+    });
+}
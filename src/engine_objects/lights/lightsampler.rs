@@ -0,0 +1,28 @@
+use crate::{Float, engine_objects::{AliasTable, bvh::AABB}};
+
+use super::Light;
+
+// Picks a few lights per shading point in proportion to their power instead of sampling every
+// light. Each pick is weighted by its inverse probability, so the average over many frames matches
+// sampling all lights.
+pub struct LightSampler {
+    pub table: AliasTable,
+    // Number of lights picked per shading point
+    pub samples: usize,
+}
+
+impl LightSampler {
+    pub fn new(lights: &Vec<Box<dyn Light>>, scene_bounds: &AABB, samples: usize) -> LightSampler {
+        let scene_radius = (scene_bounds.bmax - scene_bounds.bmin).magnitude() / 2.0;
+        let powers: Vec<f64> = lights.iter().map(|light| light.power(scene_radius)).collect();
+
+        return LightSampler {
+            table: AliasTable::new(&powers),
+            samples,
+        };
+    }
+
+    pub fn sample(self: &Self, u: Float) -> (usize, f64) {
+        return self.table.sample(u);
+    }
+}
@@ -0,0 +1,79 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::{Color, Distribution1D, Material, primitives::Primitive}};
+
+use super::{Light, LightSample};
+
+// Gathers every primitive with an emissive material into one light. Emitters are picked in
+// proportion to their power, so small or dim emitters in large meshes cost few samples. Lights are
+// sampled without a time, so moving emitters are left out and only glow where a ray hits them.
+pub struct MeshLight {
+    pub emitters: Vec<Box<dyn Primitive>>,
+    pub emissions: Vec<Color>,
+    pub distribution: Distribution1D,
+    pub samples: usize,
+}
+
+impl MeshLight {
+    pub fn new(primitives: &Vec<Box<dyn Primitive>>, materials: &Vec<Material>, samples: usize) -> Option<MeshLight> {
+        let mut emitters = Vec::new();
+        let mut emissions = Vec::new();
+        let mut powers = Vec::new();
+
+        for primitive in primitives {
+            let emission = primitive.get_material(materials).emission;
+            if emission.luminance() <= 0.0 { continue; }
+            if primitive.get_offset(0.0) != primitive.get_offset(1.0) { continue; }
+
+            powers.push(emission.luminance() * primitive.get_area() as f64);
+            emissions.push(emission);
+            emitters.push(primitive.clone_primitive());
+        }
+
+        if emitters.is_empty() { return None; }
+
+        return Some(MeshLight {
+            emitters,
+            emissions,
+            distribution: Distribution1D::new(powers),
+            samples,
+        });
+    }
+}
+
+impl Light for MeshLight {
+    fn illuminate(self: &Self, point: &Vector3<Float>, u: Float, v: Float) -> Option<LightSample> {
+        let (index, emitter_pdf, u) = self.distribution.sample_discrete(u);
+        let emitter = &self.emitters[index];
+        let (light_point, light_normal) = emitter.sample_surface(u, v);
+
+        let light_vector: Vector3<Float> = light_point - point;
+        let distance: Float = light_vector.magnitude();
+        let direction: Unit<Vector3<Float>> = Unit::new_normalize(light_vector);
+
+        let cos_light = -direction.dot(&light_normal);
+        if cos_light <= 0.0 { return None; }
+
+        // Convert the pdf of picking this emitter and a point on it to solid angle
+        let area_pdf = emitter_pdf / emitter.get_area() as f64;
+        let geometry_term = (cos_light / (distance * distance)) as f64;
+
+        return Some(LightSample {
+            direction,
+            distance,
+            energy: self.emissions[index] * (geometry_term / area_pdf),
+        });
+    }
+
+    fn sample_count(self: &Self) -> usize {
+        return self.samples;
+    }
+
+    fn power(self: &Self, _scene_radius: Float) -> f64 {
+        let mut power = 0.0;
+        for (emitter, emission) in self.emitters.iter().zip(self.emissions.iter()) {
+            power += std::f64::consts::PI * emitter.get_area() as f64 * emission.luminance();
+        }
+        return power;
+    }
+}
@@ -1,2 +1,18 @@
+mod light;
+pub use light::*;
 mod pointlight;
-pub use pointlight::*;
\ No newline at end of file
+pub use pointlight::*;
+mod directionallight;
+pub use directionallight::*;
+mod spotlight;
+pub use spotlight::*;
+mod rectlight;
+pub use rectlight::*;
+mod disklight;
+pub use disklight::*;
+mod spherelight;
+pub use spherelight::*;
+mod meshlight;
+pub use meshlight::*;
+mod lightsampler;
+pub use lightsampler::*;
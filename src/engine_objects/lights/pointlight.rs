@@ -1,25 +1,34 @@
-use nalgebra::Vector3;
+use nalgebra::{Unit, Vector3};
 
-use crate::{EPSILON, engine_objects::{Ray, Scene}};
+use crate::{Float, engine_objects::Color};
+
+use super::{Light, LightSample};
 
 pub struct PointLight {
-    pub origin: Vector3<f64>,
-    pub intensity: f64,
+    pub origin: Vector3<Float>,
+    // Radiant power per colour channel in watts, emitted equally in all directions
+    pub power: Color,
 }
 
-impl PointLight {
-    pub fn occluded(scene: &Scene, shadow_ray: &Ray, dist: f64) -> bool {
-        for primitive in &scene.primitives {
-            let material = primitive.get_material(&scene.materials);
-            if !(material.refraction_cof > EPSILON) {
-                let intersection = primitive.intersect(shadow_ray);
-                if let Some(prim_dist) = intersection {
-                    if prim_dist < dist {
-                        return true;
-                    }
-                }
-            }
-        }
-        return false;
+impl Light for PointLight {
+    fn illuminate(self: &Self, point: &Vector3<Float>, _u: Float, _v: Float) -> Option<LightSample> {
+        let light_vector: Vector3<Float> = self.origin - point;
+        let distance: Float = light_vector.magnitude();
+        let dist_falloff = 1.0 / (distance * distance) as f64;
+        let intensity = self.power * (1.0 / (4.0 * std::f64::consts::PI));
+
+        return Some(LightSample {
+            direction: Unit::new_normalize(light_vector),
+            distance,
+            energy: intensity * dist_falloff,
+        });
+    }
+
+    fn is_delta(self: &Self) -> bool {
+        return true;
     }
-}
\ No newline at end of file
+
+    fn power(self: &Self, _scene_radius: Float) -> f64 {
+        return self.power.luminance();
+    }
+}
@@ -0,0 +1,55 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::{Color, Ray}};
+
+use super::{Light, LightSample, sample_area};
+
+// Rectangular area light emitting on the side of edge_u × edge_v
+pub struct RectLight {
+    pub center: Vector3<Float>,
+    // Half of the rectangle's edges
+    pub edge_u: Vector3<Float>,
+    pub edge_v: Vector3<Float>,
+    // Emitted radiance per colour channel in W/(m²·sr)
+    pub radiance: Color,
+    pub samples: usize,
+}
+
+impl RectLight {
+    fn normal(self: &Self) -> Unit<Vector3<Float>> {
+        return Unit::new_normalize(self.edge_u.cross(&self.edge_v));
+    }
+}
+
+impl Light for RectLight {
+    fn illuminate(self: &Self, point: &Vector3<Float>, u: Float, v: Float) -> Option<LightSample> {
+        let light_point: Vector3<Float> = self.center + self.edge_u * (2.0 * u - 1.0) + self.edge_v * (2.0 * v - 1.0);
+        let area = 4.0 * self.edge_u.cross(&self.edge_v).magnitude();
+        return sample_area(point, &light_point, &self.normal(), area, self.radiance);
+    }
+
+    fn sample_count(self: &Self) -> usize {
+        return self.samples;
+    }
+
+    fn intersect(self: &Self, ray: &Ray) -> Option<(Float, Color)> {
+        let normal = self.normal();
+        let denom = ray.direction.dot(&normal);
+        if denom >= 0.0 { return None; }
+
+        let distance = (self.center - ray.origin).dot(&normal) / denom;
+        if distance <= 0.0 { return None; }
+
+        let local: Vector3<Float> = ray.get_intersection_point(distance) - self.center;
+        let u = local.dot(&self.edge_u) / self.edge_u.magnitude_squared();
+        let v = local.dot(&self.edge_v) / self.edge_v.magnitude_squared();
+        if u.abs() > 1.0 || v.abs() > 1.0 { return None; }
+
+        return Some((distance, self.radiance));
+    }
+
+    fn power(self: &Self, _scene_radius: Float) -> f64 {
+        let area = 4.0 * self.edge_u.cross(&self.edge_v).magnitude() as f64;
+        return std::f64::consts::PI * area * self.radiance.luminance();
+    }
+}
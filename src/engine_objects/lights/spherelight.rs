@@ -0,0 +1,78 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::{Color, Ray}};
+
+use super::{Light, LightSample};
+
+// Spherical area light, sampled uniformly over the cone it subtends from the shaded point
+pub struct SphereLight {
+    pub origin: Vector3<Float>,
+    pub radius: Float,
+    // Emitted radiance per colour channel in W/(m²·sr)
+    pub radiance: Color,
+    pub samples: usize,
+}
+
+impl Light for SphereLight {
+    fn illuminate(self: &Self, point: &Vector3<Float>, u: Float, v: Float) -> Option<LightSample> {
+        let pi = std::f64::consts::PI as Float;
+        let light_vector: Vector3<Float> = self.origin - point;
+        let center_distance2 = light_vector.magnitude_squared();
+        let radius2 = self.radius * self.radius;
+
+        // Points inside the light are not lit by it
+        if center_distance2 <= radius2 { return None; }
+
+        let axis: Unit<Vector3<Float>> = Unit::new_normalize(light_vector);
+        let helper = if axis.x.abs() > 0.9 { Vector3::y() } else { Vector3::x() };
+        let tangent: Vector3<Float> = axis.cross(&helper).normalize();
+        let bitangent: Vector3<Float> = axis.cross(&tangent);
+
+        let cos_theta_max = (1.0 - radius2 / center_distance2).max(0.0).sqrt();
+        let cos_theta = 1.0 - u * (1.0 - cos_theta_max);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * pi * v;
+
+        let direction: Unit<Vector3<Float>> = Unit::new_normalize(
+            tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + axis.into_inner() * cos_theta
+        );
+
+        // Distance to the near side of the sphere along the sampled direction
+        let center_distance = center_distance2.sqrt();
+        let tca = center_distance * cos_theta;
+        let d2 = center_distance2 - tca * tca;
+        let distance = tca - (radius2 - d2).max(0.0).sqrt();
+
+        let solid_angle = (2.0 * pi * (1.0 - cos_theta_max)) as f64;
+
+        return Some(LightSample {
+            direction,
+            distance,
+            energy: self.radiance * solid_angle,
+        });
+    }
+
+    fn sample_count(self: &Self) -> usize {
+        return self.samples;
+    }
+
+    fn intersect(self: &Self, ray: &Ray) -> Option<(Float, Color)> {
+        let l: Vector3<Float> = self.origin - ray.origin;
+        let tca = l.dot(&ray.direction);
+        let d2 = l.dot(&l) - tca * tca;
+        let radius2 = self.radius * self.radius;
+        if d2 > radius2 { return None; }
+
+        let thc = (radius2 - d2).sqrt();
+        let distance = if tca - thc > 0.0 { tca - thc } else { tca + thc };
+        if distance <= 0.0 { return None; }
+
+        return Some((distance, self.radiance));
+    }
+
+    fn power(self: &Self, _scene_radius: Float) -> f64 {
+        let pi = std::f64::consts::PI;
+        let area = 4.0 * pi * (self.radius * self.radius) as f64;
+        return pi * area * self.radiance.luminance();
+    }
+}
@@ -0,0 +1,66 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::{Float, engine_objects::Color};
+
+use super::{Light, LightSample};
+
+pub struct SpotLight {
+    pub origin: Vector3<Float>,
+    // Direction the spot light is pointing at
+    pub direction: Unit<Vector3<Float>>,
+    // Radiant power per colour channel in watts, spread over the cone
+    pub power: Color,
+    // Half angle of the cone in degrees, no light is emitted outside of it
+    pub cone_angle: Float,
+    // Angle in degrees at the edge of the cone over which the light fades out
+    pub penumbra_angle: Float,
+}
+
+impl SpotLight {
+    // Radiant intensity in W/sr, the penumbra counts as half lit
+    fn intensity(self: &Self) -> Color {
+        let cos_outer = self.cone_angle.to_radians().cos() as f64;
+        let cos_inner = (self.cone_angle - self.penumbra_angle).max(0.0).to_radians().cos() as f64;
+        let solid_angle = 2.0 * std::f64::consts::PI * (1.0 - 0.5 * (cos_inner + cos_outer));
+        return self.power * (1.0 / solid_angle);
+    }
+
+    fn cone_falloff(self: &Self, cos_theta: Float) -> f64 {
+        let cos_outer = self.cone_angle.to_radians().cos();
+        let cos_inner = (self.cone_angle - self.penumbra_angle).max(0.0).to_radians().cos();
+
+        if cos_theta <= cos_outer { return 0.0; }
+        if cos_theta >= cos_inner { return 1.0; }
+
+        // Smoothstep between the outer and inner cone
+        let t = ((cos_theta - cos_outer) / (cos_inner - cos_outer)) as f64;
+        return t * t * (3.0 - 2.0 * t);
+    }
+}
+
+impl Light for SpotLight {
+    fn illuminate(self: &Self, point: &Vector3<Float>, _u: Float, _v: Float) -> Option<LightSample> {
+        let light_vector: Vector3<Float> = self.origin - point;
+        let distance: Float = light_vector.magnitude();
+        let direction: Unit<Vector3<Float>> = Unit::new_normalize(light_vector);
+
+        let cone_falloff = self.cone_falloff(-direction.dot(&self.direction));
+        if cone_falloff <= 0.0 { return None; }
+
+        let dist_falloff = 1.0 / (distance * distance) as f64;
+
+        return Some(LightSample {
+            direction,
+            distance,
+            energy: self.intensity() * (dist_falloff * cone_falloff),
+        });
+    }
+
+    fn is_delta(self: &Self) -> bool {
+        return true;
+    }
+
+    fn power(self: &Self, _scene_radius: Float) -> f64 {
+        return self.power.luminance();
+    }
+}
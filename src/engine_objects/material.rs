@@ -1,4 +1,8 @@
-use super::Color;
+use nalgebra::{Vector2, Vector3};
+
+use crate::Float;
+
+use super::{Color, bsdfs::{Ggx, Lambert}, textures::{Texture, TextureCoordinates}};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Material {
@@ -6,6 +10,234 @@ pub struct Material {
     pub refraction_index: Option<f64>,
     pub specular_cof: f64,
     pub refraction_cof: f64,
+    // Roughness of the refracting surface, perfectly smooth glass when zero
+    pub refraction_roughness: f64,
+    // Blinn-Phong highlight of the direct light, the highlight is left out when shininess is zero
+    pub specular_color: Color,
+    pub shininess: f64,
+    // Radiance emitted by the surface in W/(m²·sr), black for surfaces that do not glow. Emission is
+    // one-sided, leaving the surface on the side its normal points to
+    pub emission: Color,
+    // Attenuation of light travelling through the inside of a transmissive material
+    pub absorption: Option<Absorption>,
+    // Physically based shading in place of the Lambert and Blinn-Phong terms
+    pub metallic_roughness: Option<MetallicRoughness>,
+    // Wavelength dependent index of refraction, used instead of `refraction_index` when the ray
+    // carries a wavelength
+    pub dispersion: Option<Dispersion>,
+    // Textures that vary the material's channels over its surface
+    pub textures: MaterialTextures,
     // Alpha: 1.0 = Opaque, Alpha: 0.0 = Transparent
-    // pub alpha: f64,
-}
\ No newline at end of file
+    // Surfaces are cut away where alpha, times the alpha texture, falls below ALPHA_CUTOFF
+    pub alpha: f64,
+}
+
+impl Material {
+    // Copy of the material with its textured channels looked up at the texture coordinates
+    pub fn apply_textures(self: &Self, textures: &Vec<Box<dyn Texture>>, coordinates: &TextureCoordinates) -> Material {
+        let lookup = |index: Option<usize>| index.map(|index| textures[index].evaluate(coordinates));
+        let mut material = *self;
+
+        if let Some(albedo) = lookup(self.textures.albedo) {
+            material.diffuse_color = material.diffuse_color * albedo;
+        }
+        if let Some(specular) = lookup(self.textures.specular) {
+            material.specular_color = material.specular_color * specular;
+        }
+        if let Some(emission) = lookup(self.textures.emission) {
+            material.emission = material.emission * emission;
+        }
+        // Like glTF, roughness is read from the green and metalness from the blue channel
+        if let Some(roughness) = lookup(self.textures.roughness) {
+            material.refraction_roughness *= roughness.green;
+            if let Some(metallic_roughness) = &mut material.metallic_roughness {
+                metallic_roughness.roughness *= roughness.green;
+                metallic_roughness.metallic *= roughness.blue;
+            }
+        }
+        return material;
+    }
+
+    // Shading normal after applying the bump map and then the normal map, `dpdu` and `dpdv` being
+    // the derivatives of the surface position with respect to the texture coordinates
+    pub fn perturb_normal(self: &Self, textures: &Vec<Box<dyn Texture>>, coordinates: &TextureCoordinates, normal: &Vector3<Float>, dpdu: &Vector3<Float>, dpdv: &Vector3<Float>) -> Vector3<Float> {
+        let mut shading_normal = *normal;
+
+        if let Some(index) = self.textures.bump {
+            let height = |du: Float, dv: Float| -> Float {
+                let shifted = TextureCoordinates {
+                    uv: coordinates.uv + Vector2::new(du, dv),
+                    uv_footprint: coordinates.uv_footprint,
+                    point: coordinates.point + dpdu * du + dpdv * dv,
+                    point_footprint: coordinates.point_footprint,
+                };
+                return (textures[index].evaluate(&shifted).luminance() * self.textures.bump_height) as Float;
+            };
+
+            // Differences over half the footprint, so the bumps are as filtered as the texture
+            let delta = (coordinates.uv_footprint * 0.5).max(0.0005);
+            let base = height(0.0, 0.0);
+            let dhdu = (height(delta, 0.0) - base) / delta;
+            let dhdv = (height(0.0, delta) - base) / delta;
+
+            // Displace the surface along the normal and take the normal of the displaced surface
+            let bumped: Vector3<Float> = (dpdu + normal * dhdu).cross(&(dpdv + normal * dhdv));
+            if bumped.magnitude_squared() > 0.0 {
+                let bumped = bumped.normalize();
+                shading_normal = if bumped.dot(normal) < 0.0 { -bumped } else { bumped };
+            }
+        }
+
+        if let Some(index) = self.textures.normal {
+            // MikkTSpace frame: the tangent is made orthogonal to the normal and the bitangent is
+            // rebuilt from both, keeping the handedness of the texture mapping
+            let tangent: Vector3<Float> = (dpdu - shading_normal * shading_normal.dot(dpdu)).normalize();
+            let handedness = if shading_normal.cross(&tangent).dot(dpdv) < 0.0 { -1.0 } else { 1.0 };
+            let bitangent: Vector3<Float> = shading_normal.cross(&tangent) * handedness;
+
+            let texel = textures[index].evaluate(coordinates);
+            let x = (texel.red * 2.0 - 1.0) as Float;
+            let y = (texel.green * 2.0 - 1.0) as Float;
+            let z = (texel.blue * 2.0 - 1.0) as Float;
+            // Green points towards decreasing v
+            shading_normal = (tangent * x - bitangent * y + shading_normal * z).normalize();
+        }
+
+        return shading_normal;
+    }
+
+    // Index of refraction at the given wavelength in nanometres, or the plain index when the
+    // material does not disperse or the ray is not spectral
+    pub fn get_refraction_index(self: &Self, wavelength: Option<f64>) -> f64 {
+        if let (Some(dispersion), Some(wavelength)) = (self.dispersion, wavelength) {
+            return dispersion.refraction_index(wavelength);
+        }
+        return self.refraction_index
+            .expect("No refraction index is defined for this material");
+    }
+}
+
+// Indices into the scene's textures, each multiplying the matching constant of the material
+#[derive(Clone, Copy, Debug)]
+pub struct MaterialTextures {
+    pub albedo: Option<usize>,
+    pub roughness: Option<usize>,
+    pub specular: Option<usize>,
+    pub emission: Option<usize>,
+    // Tangent space normal map stored linearly, with blue pointing out of the surface and green
+    // up the image, towards decreasing v
+    pub normal: Option<usize>,
+    // Height map, whose luminance is scaled by `bump_height` in world units
+    pub bump: Option<usize>,
+    pub bump_height: f64,
+    // Opacity multiplying the material's alpha
+    pub alpha: Option<usize>,
+}
+
+impl MaterialTextures {
+    pub fn none() -> MaterialTextures {
+        return MaterialTextures {
+            albedo: None,
+            roughness: None,
+            specular: None,
+            emission: None,
+            normal: None,
+            bump: None,
+            bump_height: 0.0,
+            alpha: None,
+        };
+    }
+
+    pub fn is_empty(self: &Self) -> bool {
+        return self.albedo.is_none() && self.roughness.is_none() && self.specular.is_none() && self.emission.is_none()
+            && !self.perturbs_normal();
+    }
+
+    pub fn perturbs_normal(self: &Self) -> bool {
+        return self.normal.is_some() || self.bump.is_some();
+    }
+}
+
+// Parameters of the glTF metallic-roughness model, the material's `diffuse_color` is the base colour
+#[derive(Clone, Copy, Debug)]
+pub struct MetallicRoughness {
+    pub metallic: f64,
+    pub roughness: f64,
+}
+
+impl MetallicRoughness {
+    // Splits the surface into its diffuse and GGX specular lobes. Dielectrics reflect 4% at normal
+    // incidence, metals tint their reflection by the base colour and have no diffuse part.
+    pub fn lobes(self: &Self, base_color: Color) -> (Lambert, Ggx) {
+        let dielectric_f0 = 0.04;
+        let diffuse = Lambert {
+            albedo: base_color * ((1.0 - self.metallic) * (1.0 - dielectric_f0)),
+        };
+        let specular = Ggx {
+            f0: Color::white() * (dielectric_f0 * (1.0 - self.metallic)) + base_color * self.metallic,
+            alpha: (self.roughness * self.roughness).max(0.001),
+        };
+        return (diffuse, specular);
+    }
+}
+
+// Coefficients of an index of refraction that changes with the wavelength, both fits take the
+// wavelength in micrometres
+#[derive(Clone, Copy, Debug)]
+pub enum Dispersion {
+    // n = a + b / λ²
+    Cauchy { a: f64, b: f64 },
+    // n² = 1 + Σ b·λ² / (λ² - c)
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    // Diamond, which splits white light far more than glass
+    pub fn diamond() -> Dispersion {
+        return Dispersion::Sellmeier {
+            b: [0.3306, 4.3356, 0.0],
+            c: [0.030625, 0.011236, 0.0],
+        };
+    }
+
+    pub fn refraction_index(self: &Self, wavelength: f64) -> f64 {
+        let lambda = wavelength * 1e-3;
+        let lambda2 = lambda * lambda;
+        return match self {
+            Dispersion::Cauchy { a, b } => a + b / lambda2,
+            Dispersion::Sellmeier { b, c } => {
+                let mut n2 = 1.0;
+                for i in 0..3 {
+                    n2 += b[i] * lambda2 / (lambda2 - c[i]);
+                }
+                n2.sqrt()
+            }
+        };
+    }
+}
+
+// Alpha below which a surface counts as a hole, as in glTF's mask mode
+pub static ALPHA_CUTOFF: f64 = 0.5;
+
+// Beer-Lambert absorption inside a medium. Light keeps `color` of its energy after travelling one
+// unit of distance at a density of one, so thick parts of an object appear more saturated.
+#[derive(Clone, Copy, Debug)]
+pub struct Absorption {
+    pub color: Color,
+    pub density: f64,
+}
+
+impl Absorption {
+    pub fn transmittance(self: &Self, distance: f64) -> Color {
+        let channel = |c: f64| -> f64 {
+            if c <= 0.0 { return 0.0; }
+            return (c.ln() * self.density * distance).exp();
+        };
+
+        return Color {
+            red: channel(self.color.red),
+            green: channel(self.color.green),
+            blue: channel(self.color.blue),
+        };
+    }
+}
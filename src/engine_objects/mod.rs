@@ -1,5 +1,9 @@
 mod camera;
 pub use camera::*;
+mod controls;
+pub use controls::*;
+mod camerapath;
+pub use camerapath::*;
 mod color;
 pub use color::*;
 mod ray;
@@ -8,10 +12,23 @@ mod scene;
 pub use scene::*;
 mod screen;
 pub use screen::*;
+mod projection;
+pub use projection::*;
+mod stereo;
+pub use stereo::*;
 pub mod primitives;
 pub mod lights;
+pub mod environments;
 mod material;
 pub use material::*;
+mod spectrum;
+pub use spectrum::*;
+mod distribution;
+pub use distribution::*;
+mod random;
+pub use random::*;
 mod macros;
 pub use macros::*;
-pub mod bvh;
\ No newline at end of file
+pub mod bvh;
+pub mod bsdfs;
+pub mod textures;
\ No newline at end of file
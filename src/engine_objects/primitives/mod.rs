@@ -3,4 +3,6 @@ pub use primitive::*;
 mod sphere;
 pub use sphere::*;
 mod triangle;
-pub use triangle::*;
\ No newline at end of file
+pub use triangle::*;
+mod moving;
+pub use moving::*;
\ No newline at end of file
@@ -0,0 +1,84 @@
+use nalgebra::{Unit, Vector2, Vector3};
+
+use crate::{Float, engine_objects::{Material, Ray, bvh::AABB}};
+
+use super::primitive::Primitive;
+
+// Primitive that slides in a straight line from being moved by `start` at time zero to being moved
+// by `end` at time one. The wrapped primitive is described at its position without any offset.
+pub struct Moving {
+    pub primitive: Box<dyn Primitive>,
+    pub start: Vector3<Float>,
+    pub end: Vector3<Float>,
+}
+
+impl Primitive for Moving {
+    fn intersect(self: &Self, ray: &Ray) -> Option<Float> {
+        // Moving the ray the other way is the same as moving the primitive
+        let mut local_ray = Ray::new();
+        local_ray.origin = ray.origin - self.get_offset(ray.time);
+        local_ray.direction = ray.direction;
+        return self.primitive.intersect(&local_ray);
+    }
+
+    fn get_offset(self: &Self, time: Float) -> Vector3<Float> {
+        return self.start + (self.end - self.start) * time;
+    }
+
+    fn get_material(self: &Self, materials: &Vec<Material>) -> Material {
+        return self.primitive.get_material(materials);
+    }
+
+    fn get_normal(self: &Self, intersection_point: &Vector3<Float>) -> Unit<Vector3<Float>> {
+        return self.primitive.get_normal(intersection_point);
+    }
+
+    fn refine_intersection(self: &Self, intersection_point: &Vector3<Float>) -> (Vector3<Float>, Vector3<Float>) {
+        return self.primitive.refine_intersection(intersection_point);
+    }
+
+    fn get_uv(self: &Self, intersection_point: &Vector3<Float>) -> Vector2<Float> {
+        return self.primitive.get_uv(intersection_point);
+    }
+
+    fn get_uv_scale(self: &Self) -> Float {
+        return self.primitive.get_uv_scale();
+    }
+
+    fn get_tangents(self: &Self, intersection_point: &Vector3<Float>) -> (Vector3<Float>, Vector3<Float>) {
+        return self.primitive.get_tangents(intersection_point);
+    }
+
+    fn get_centroid(self: &Self) -> Vector3<Float> {
+        return self.primitive.get_centroid() + (self.start + self.end) / 2.0;
+    }
+
+    // Bounds the primitive over its whole path, so the BVH finds it at any time
+    fn get_bounds(self: &Self) -> AABB {
+        let bounds = self.primitive.get_bounds();
+        let mut motion_bounds = AABB::new();
+        for offset in [self.start, self.end].iter() {
+            motion_bounds.grow_by_point(&(bounds.bmin + offset));
+            motion_bounds.grow_by_point(&(bounds.bmax + offset));
+        }
+        return motion_bounds;
+    }
+
+    fn get_area(self: &Self) -> Float {
+        return self.primitive.get_area();
+    }
+
+    // Samples the surface where it is at time zero
+    fn sample_surface(self: &Self, u: Float, v: Float) -> (Vector3<Float>, Unit<Vector3<Float>>) {
+        let (point, normal) = self.primitive.sample_surface(u, v);
+        return (point + self.start, normal);
+    }
+
+    fn clone_primitive(self: &Self) -> Box<dyn Primitive> {
+        return Box::new(Moving {
+            primitive: self.primitive.clone_primitive(),
+            start: self.start,
+            end: self.end,
+        });
+    }
+}
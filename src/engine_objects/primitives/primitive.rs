@@ -1,11 +1,27 @@
-use nalgebra::{Unit, Vector3};
+use nalgebra::{Unit, Vector2, Vector3};
 
-use crate::engine_objects::{Material, Ray, bvh::AABB};
+use crate::{Float, engine_objects::{Material, Ray, bvh::AABB}};
 
 pub trait Primitive {
-    fn intersect(self: &Self, ray: &Ray) -> Option<f64>;
+    fn intersect(self: &Self, ray: &Ray) -> Option<Float>;
+    // How far the primitive has moved at the time. The methods taking a point on the surface expect
+    // it with this offset taken off.
+    fn get_offset(self: &Self, _time: Float) -> Vector3<Float> { return Vector3::zeros(); }
     fn get_material(self: &Self, materials: &Vec<Material>) -> Material;
-    fn get_normal(self: &Self, intersection_point: &Vector3<f64>) -> Unit<Vector3<f64>>;
-    fn get_centroid(self: &Self) -> Vector3<f64>;
+    fn get_normal(self: &Self, intersection_point: &Vector3<Float>) -> Unit<Vector3<Float>>;
+    // Recomputes the intersection point on the surface and returns it with its absolute error bound
+    fn refine_intersection(self: &Self, intersection_point: &Vector3<Float>) -> (Vector3<Float>, Vector3<Float>);
+    // Texture coordinates of a point on the surface
+    fn get_uv(self: &Self, intersection_point: &Vector3<Float>) -> Vector2<Float>;
+    // Texture coordinate units per unit of length along the surface, to turn a ray's footprint into
+    // a footprint on the texture
+    fn get_uv_scale(self: &Self) -> Float;
+    // Derivatives of the surface position with respect to u and v, to orient normal and bump maps
+    fn get_tangents(self: &Self, intersection_point: &Vector3<Float>) -> (Vector3<Float>, Vector3<Float>);
+    fn get_centroid(self: &Self) -> Vector3<Float>;
     fn get_bounds(self: &Self) -> AABB;
+    fn get_area(self: &Self) -> Float;
+    // Maps (u, v) in [0, 1)² uniformly onto the surface, returning the point and its normal
+    fn sample_surface(self: &Self, u: Float, v: Float) -> (Vector3<Float>, Unit<Vector3<Float>>);
+    fn clone_primitive(self: &Self) -> Box<dyn Primitive>;
 }
\ No newline at end of file
@@ -1,20 +1,21 @@
-use nalgebra::{Unit, Vector3};
+use nalgebra::{Unit, Vector2, Vector3};
 
-use crate::engine_objects::{Material, Ray, bvh::AABB};
+use crate::{EPSILON, Float, engine_objects::{Material, Ray, bvh::AABB, gamma}};
 
 use super::primitive::Primitive;
 
+#[derive(Clone)]
 pub struct Sphere {
-    pub origin: Vector3<f64>,
-    pub radius2: f64,
+    pub origin: Vector3<Float>,
+    pub radius2: Float,
     pub material_index: usize,
 }
 
 impl Primitive for Sphere {
-    fn intersect(self: &Self, ray: &Ray) -> Option<f64> {
-        let mut t0: f64; let mut t1: f64;
+    fn intersect(self: &Self, ray: &Ray) -> Option<Float> {
+        let mut t0: Float; let mut t1: Float;
 
-        let l: Vector3<f64> = self.origin - ray.origin;
+        let l: Vector3<Float> = self.origin - ray.origin;
         let tca = l.dot(&ray.direction);
 
         if tca < 0.0 { return None; }
@@ -41,11 +42,43 @@ impl Primitive for Sphere {
         return materials[self.material_index];
     }
 
-    fn get_normal(&self, intersection_point: &Vector3<f64>) -> Unit<Vector3<f64>> {
+    fn get_normal(&self, intersection_point: &Vector3<Float>) -> Unit<Vector3<Float>> {
         return Unit::new_normalize(intersection_point - self.origin);
     }
 
-    fn get_centroid(self: &Self) -> Vector3<f64> {
+    fn refine_intersection(self: &Self, intersection_point: &Vector3<Float>) -> (Vector3<Float>, Vector3<Float>) {
+        // Reproject the point onto the sphere to remove the error accumulated along the ray
+        let local: Vector3<Float> = intersection_point - self.origin;
+        let local: Vector3<Float> = local * (self.radius2.sqrt() / local.magnitude());
+        let point_error: Vector3<Float> = local.abs() * gamma(5);
+        return (self.origin + local, point_error);
+    }
+
+    // Latitude-longitude mapping with +Y as the pole, u wraps around and v runs from top to bottom
+    fn get_uv(self: &Self, intersection_point: &Vector3<Float>) -> Vector2<Float> {
+        let pi = std::f64::consts::PI as Float;
+        let direction: Vector3<Float> = (intersection_point - self.origin).normalize();
+        let u = 0.5 + direction.x.atan2(direction.z) / (2.0 * pi);
+        let v = direction.y.max(-1.0).min(1.0).acos() / pi;
+        return Vector2::new(u, v);
+    }
+
+    fn get_uv_scale(self: &Self) -> Float {
+        // The unit square is stretched over the whole surface, on average
+        return (1.0 / self.get_area()).sqrt();
+    }
+
+    fn get_tangents(self: &Self, intersection_point: &Vector3<Float>) -> (Vector3<Float>, Vector3<Float>) {
+        let pi = std::f64::consts::PI as Float;
+        let local: Vector3<Float> = intersection_point - self.origin;
+        // Distance from the pole axis, kept above zero so the poles still get a direction
+        let rho = (local.x * local.x + local.z * local.z).sqrt().max(EPSILON);
+        let dpdu = Vector3::new(local.z, 0.0, -local.x) * (2.0 * pi);
+        let dpdv = Vector3::new(local.y * local.x / rho, -rho, local.y * local.z / rho) * pi;
+        return (dpdu, dpdv);
+    }
+
+    fn get_centroid(self: &Self) -> Vector3<Float> {
         return self.origin;
     }
 
@@ -58,4 +91,22 @@ impl Primitive for Sphere {
             bmax,
         };
     }
+
+    fn get_area(self: &Self) -> Float {
+        let pi = std::f64::consts::PI as Float;
+        return 4.0 * pi * self.radius2;
+    }
+
+    fn sample_surface(self: &Self, u: Float, v: Float) -> (Vector3<Float>, Unit<Vector3<Float>>) {
+        let pi = std::f64::consts::PI as Float;
+        let z = 1.0 - 2.0 * u;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * pi * v;
+        let normal: Unit<Vector3<Float>> = Unit::new_normalize(Vector3::new(r * phi.cos(), r * phi.sin(), z));
+        return (self.origin + normal.scale(self.radius2.sqrt()), normal);
+    }
+
+    fn clone_primitive(self: &Self) -> Box<dyn Primitive> {
+        return Box::new(self.clone());
+    }
 }
\ No newline at end of file
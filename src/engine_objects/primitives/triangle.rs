@@ -1,23 +1,32 @@
-use nalgebra::{Unit, Vector3};
+use nalgebra::{Unit, Vector2, Vector3};
 
-use crate::{EPSILON, engine_objects::{Material, Ray, bvh::AABB}};
+use crate::{EPSILON, Float, engine_objects::{Material, Ray, bsdfs::tangent_frame, bvh::AABB, gamma}};
 
 use super::Primitive;
 
+#[derive(Clone)]
 pub struct Triangle {
-    pub v0: Vector3<f64>,
-    pub v1: Vector3<f64>,
-    pub v2: Vector3<f64>,
-    pub v0v1: Vector3<f64>,
-    pub v0v2: Vector3<f64>,
-    pub centroid: Vector3<f64>,
+    pub v0: Vector3<Float>,
+    pub v1: Vector3<Float>,
+    pub v2: Vector3<Float>,
+    pub v0v1: Vector3<Float>,
+    pub v0v2: Vector3<Float>,
+    pub centroid: Vector3<Float>,
     pub bounds: AABB,
     pub flip_normal: bool,
     pub material_index: usize,
+    // Texture coordinates at the vertices
+    pub uv0: Vector2<Float>,
+    pub uv1: Vector2<Float>,
+    pub uv2: Vector2<Float>,
 }
 
 impl Triangle {
-    pub fn create_triangle(v0: Vector3<f64>, v1: Vector3<f64>, v2: Vector3<f64>, flip_normal: bool, material_index: usize) -> Triangle {
+    pub fn create_triangle(v0: Vector3<Float>, v1: Vector3<Float>, v2: Vector3<Float>, flip_normal: bool, material_index: usize) -> Triangle {
+        return Triangle::create_textured_triangle(v0, v1, v2, Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0), Vector2::new(0.0, 1.0), flip_normal, material_index);
+    }
+
+    pub fn create_textured_triangle(v0: Vector3<Float>, v1: Vector3<Float>, v2: Vector3<Float>, uv0: Vector2<Float>, uv1: Vector2<Float>, uv2: Vector2<Float>, flip_normal: bool, material_index: usize) -> Triangle {
         let mut bounds = AABB::new();
         bounds.grow_by_point(&v0);
         bounds.grow_by_point(&v1);
@@ -34,8 +43,21 @@ impl Triangle {
             bounds,
             flip_normal,
             material_index,
+            uv0,
+            uv1,
+            uv2,
         }
     }
+
+    // Barycentric coordinates of a point in the triangle's plane
+    fn barycentric(self: &Self, point: &Vector3<Float>) -> (Float, Float, Float) {
+        let normal: Vector3<Float> = self.v0v1.cross(&self.v0v2);
+        let area2: Float = normal.magnitude_squared();
+        let to_point: Vector3<Float> = point - self.v0;
+        let b1: Float = to_point.cross(&self.v0v2).dot(&normal) / area2;
+        let b2: Float = self.v0v1.cross(&to_point).dot(&normal) / area2;
+        return (1.0 - b1 - b2, b1, b2);
+    }
 }
 
 impl Primitive for Triangle {
@@ -43,39 +65,97 @@ impl Primitive for Triangle {
         return self.bounds;
     }
 
-    fn get_centroid(self: &Self) -> Vector3<f64> {
+    fn get_centroid(self: &Self) -> Vector3<Float> {
         return self.centroid;
     }
 
+    fn get_area(self: &Self) -> Float {
+        return 0.5 * self.v0v1.cross(&self.v0v2).magnitude();
+    }
+
+    fn get_uv(self: &Self, intersection_point: &Vector3<Float>) -> Vector2<Float> {
+        let (b0, b1, b2) = self.barycentric(intersection_point);
+        return self.uv0 * b0 + self.uv1 * b1 + self.uv2 * b2;
+    }
+
+    fn get_tangents(self: &Self, intersection_point: &Vector3<Float>) -> (Vector3<Float>, Vector3<Float>) {
+        // Solve the edges for the directions in which u and v increase, like MikkTSpace does per face
+        let uv0uv1: Vector2<Float> = self.uv1 - self.uv0;
+        let uv0uv2: Vector2<Float> = self.uv2 - self.uv0;
+        let determinant = uv0uv1.x * uv0uv2.y - uv0uv1.y * uv0uv2.x;
+        // Compared with the edges' lengths, so the small UV triangles of atlases still count, and
+        // negated so a NaN falls back too
+        if !(determinant.abs() > EPSILON * uv0uv1.magnitude() * uv0uv2.magnitude()) {
+            // Degenerate mapping, any frame around the normal will do
+            let (tangent, bitangent) = tangent_frame(&self.get_normal(intersection_point));
+            return (tangent, bitangent);
+        }
+
+        let dpdu = (self.v0v1 * uv0uv2.y - self.v0v2 * uv0uv1.y) / determinant;
+        let dpdv = (self.v0v2 * uv0uv1.x - self.v0v1 * uv0uv2.x) / determinant;
+        return (dpdu, dpdv);
+    }
+
+    fn get_uv_scale(self: &Self) -> Float {
+        let uv0uv1: Vector2<Float> = self.uv1 - self.uv0;
+        let uv0uv2: Vector2<Float> = self.uv2 - self.uv0;
+        let uv_area = 0.5 * (uv0uv1.x * uv0uv2.y - uv0uv1.y * uv0uv2.x).abs();
+        return (uv_area / self.get_area()).sqrt();
+    }
+
+    fn sample_surface(self: &Self, u: Float, v: Float) -> (Vector3<Float>, Unit<Vector3<Float>>) {
+        // Fold the unit square onto the triangle with uniform density
+        let su = u.sqrt();
+        let b1 = 1.0 - su;
+        let b2 = v * su;
+        let point: Vector3<Float> = self.v0 + self.v0v1 * b1 + self.v0v2 * b2;
+        return (point, self.get_normal(&point));
+    }
+
+    fn clone_primitive(self: &Self) -> Box<dyn Primitive> {
+        return Box::new(self.clone());
+    }
+
     fn get_material(&self, materials: &Vec<Material>) -> Material {
         return materials[self.material_index];
     }
 
-    fn get_normal(&self, _intersection_point: &Vector3<f64>) -> Unit<Vector3<f64>> {
+    fn get_normal(&self, _intersection_point: &Vector3<Float>) -> Unit<Vector3<Float>> {
         let normal = (self.v1 - self.v0).cross(&(self.v2 - self.v0));
         return Unit::new_normalize(if self.flip_normal { -normal } else { normal });
     }
 
-    fn intersect(&self, ray: &Ray) -> Option<f64> {
-        let pvec: Vector3<f64> = ray.direction.cross(&self.v0v2);
-        let det: f64           = self.v0v1.dot(&pvec);
+    fn refine_intersection(self: &Self, intersection_point: &Vector3<Float>) -> (Vector3<Float>, Vector3<Float>) {
+        // Interpolate the point from the barycentric coordinates so it lies in the triangle's plane
+        let (b0, b1, b2) = self.barycentric(intersection_point);
+
+        let p0: Vector3<Float> = self.v0 * b0;
+        let p1: Vector3<Float> = self.v1 * b1;
+        let p2: Vector3<Float> = self.v2 * b2;
+        let point_error: Vector3<Float> = (p0.abs() + p1.abs() + p2.abs()) * gamma(7);
+        return (p0 + p1 + p2, point_error);
+    }
+
+    fn intersect(&self, ray: &Ray) -> Option<Float> {
+        let pvec: Vector3<Float> = ray.direction.cross(&self.v0v2);
+        let det: Float           = self.v0v1.dot(&pvec);
 
         if det < EPSILON && det > -EPSILON { return None; }
 
-        let inv_det: f64       = 1.0 / det;
-        let tvec: Vector3<f64> = ray.origin - self.v0;
-        let u: f64             = tvec.dot(&pvec) * inv_det;
+        let inv_det: Float       = 1.0 / det;
+        let tvec: Vector3<Float> = ray.origin - self.v0;
+        let u: Float             = tvec.dot(&pvec) * inv_det;
 
         if u < 0.0 || u > 1.0 { return None; }
 
-        let qvec: Vector3<f64> = tvec.cross(&self.v0v1);
-        let v: f64             = ray.direction.dot(&qvec) * inv_det;
+        let qvec: Vector3<Float> = tvec.cross(&self.v0v1);
+        let v: Float             = ray.direction.dot(&qvec) * inv_det;
 
         if v < 0.0 || u + v > 1.0 { return None; }
 
-        let distance: f64      = self.v0v2.dot(&qvec) * inv_det;
+        let distance: Float      = self.v0v2.dot(&qvec) * inv_det;
 
-        if distance < EPSILON { return None; }
+        if distance <= 0.0 { return None; }
 
         return Some(distance);
     }
@@ -0,0 +1,118 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::Float;
+
+use super::{Camera, Screen};
+
+// How positions in the image map to rays leaving the camera
+#[derive(Clone, Copy, Debug)]
+pub enum Projection {
+    // Planar pinhole projection through the screen, the only one the thin lens applies to
+    Perspective,
+    // Parallel rays covering a view of the given height in world units
+    Orthographic { height: Float },
+    // Circular image covering the camera's field of view, the area outside the circle is empty
+    Fisheye { mapping: FisheyeMapping },
+    // Full panorama with longitude across and latitude down the image
+    Equirectangular,
+    // The six faces of a cube around the camera laid out on a 3 × 2 grid, in the order +x, -x, +y
+    // on the top row and -y, +z, -z on the bottom one, where z is forward and y up
+    Cubemap,
+}
+
+// Relation between the distance from the centre of a fisheye image and the angle from the view
+// direction
+#[derive(Clone, Copy, Debug)]
+pub enum FisheyeMapping {
+    // The distance grows linearly with the angle
+    Equidistant,
+    // Equal areas of the image cover equal solid angles
+    Equisolid,
+}
+
+impl Projection {
+    pub fn from_name(name: &str) -> Option<Projection> {
+        return match name {
+            "perspective" => Some(Projection::Perspective),
+            "orthographic" => Some(Projection::Orthographic { height: 8.0 }),
+            "fisheye" => Some(Projection::Fisheye { mapping: FisheyeMapping::Equidistant }),
+            "fisheye-equisolid" => Some(Projection::Fisheye { mapping: FisheyeMapping::Equisolid }),
+            "equirectangular" => Some(Projection::Equirectangular),
+            "cubemap" => Some(Projection::Cubemap),
+            _ => None,
+        };
+    }
+
+    // Origin and direction of the ray through the image position, with u going right and v down
+    // from zero to one. Positions outside the projected image give no ray.
+    pub fn generate(self: &Self, camera: &Camera, screen: &Screen, u: Float, v: Float) -> Option<(Vector3<Float>, Unit<Vector3<Float>>)> {
+        let pi = std::f64::consts::PI as Float;
+        let (right, up, forward) = camera.axes();
+        // Centred coordinates, one unit from the centre to the top edge
+        let x = (2.0 * u - 1.0) * screen.ratio;
+        let y = 1.0 - 2.0 * v;
+
+        return match self {
+            Projection::Perspective => {
+                let point_on_screen: Vector3<Float> = screen.p0 + u * (screen.p1 - screen.p0) + v * (screen.p2 - screen.p0);
+                Some((camera.origin, Unit::new_normalize(point_on_screen - camera.origin)))
+            }
+            Projection::Orthographic { height } => {
+                let origin = camera.origin + (right * x + up * y) * (height / 2.0);
+                Some((origin, Unit::new_normalize(forward)))
+            }
+            Projection::Fisheye { mapping } => {
+                let radius = (x * x + y * y).sqrt();
+                if radius > 1.0 { return None; }
+
+                // Beyond 360° the image would wrap around onto itself
+                let max_angle = (camera.fov.to_radians() / 2.0).min(pi);
+                let angle = match mapping {
+                    FisheyeMapping::Equidistant => radius * max_angle,
+                    FisheyeMapping::Equisolid => 2.0 * (radius * (max_angle / 2.0).sin()).min(1.0).asin(),
+                };
+                let sideways = if radius > 0.0 { (right * x + up * y) / radius } else { Vector3::zeros() };
+                Some((camera.origin, Unit::new_normalize(forward * angle.cos() + sideways * angle.sin())))
+            }
+            Projection::Equirectangular => {
+                let longitude = (u - 0.5) * 2.0 * pi;
+                let latitude = (0.5 - v) * pi;
+                let direction = (forward * longitude.cos() + right * longitude.sin()) * latitude.cos() + up * latitude.sin();
+                Some((camera.origin, Unit::new_normalize(direction)))
+            }
+            Projection::Cubemap => {
+                let column = (u * 3.0).floor().min(2.0);
+                let row = (v * 2.0).floor().min(1.0);
+                let s = (u * 3.0 - column) * 2.0 - 1.0;
+                let t = (v * 2.0 - row) * 2.0 - 1.0;
+                // Face directions in the (right, up, forward) frame, following the OpenGL convention
+                let (a, b, c) = match (row as usize) * 3 + column as usize {
+                    0 => (1.0, -t, -s),
+                    1 => (-1.0, -t, s),
+                    2 => (s, 1.0, t),
+                    3 => (s, -1.0, -t),
+                    4 => (s, -t, 1.0),
+                    _ => (-s, -t, -1.0),
+                };
+                Some((camera.origin, Unit::new_normalize(right * a + up * b + forward * c)))
+            }
+        };
+    }
+
+    // Width of the ray cone through a pixel at the camera and the angle it spreads by
+    pub fn pixel_cone(self: &Self, camera: &Camera, screen: &Screen) -> (Float, Float) {
+        let pi = std::f64::consts::PI as Float;
+        let pixel = 2.0 / screen.height as Float;
+
+        return match self {
+            Projection::Perspective => {
+                let distance = (screen.center - camera.origin).magnitude();
+                (0.0, ((screen.p1 - screen.p0).magnitude() / screen.width as Float) / distance)
+            }
+            Projection::Orthographic { height } => (pixel * height / 2.0, 0.0),
+            Projection::Fisheye { .. } => (0.0, pixel * camera.fov.to_radians() / 2.0),
+            Projection::Equirectangular => (0.0, pi / screen.height as Float),
+            Projection::Cubemap => (0.0, (pi / 2.0) / (screen.height as Float / 2.0)),
+        };
+    }
+}
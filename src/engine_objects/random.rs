@@ -0,0 +1,19 @@
+use std::cell::Cell;
+
+use crate::Float;
+
+thread_local! {
+    static STATE: Cell<u64> = Cell::new(0x853c49e6748fea9b);
+}
+
+// Uniform random number in [0, 1) from a per-thread xorshift generator
+pub fn random_float() -> Float {
+    return STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        ((x >> 11) as f64 / (1u64 << 53) as f64) as Float
+    });
+}
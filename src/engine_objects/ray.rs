@@ -1,12 +1,25 @@
 use nalgebra::{Unit, Vector3, clamp};
 
-use crate::{EPSILON, RECURSION_LIMIT};
+use crate::{EPSILON, Float, RECURSION_LIMIT, SHADOW_EPSILON};
 
-use super::{Camera, Color, Material, Scene, Screen, bvh::AABB, lights::PointLight};
+use super::{Camera, Color, Material, MetallicRoughness, Projection, Scene, Screen, WAVELENGTH_MAX, WAVELENGTH_MIN, bsdfs::{Bsdf, Lambert, Phong, RoughDielectric}, bvh::AABB, lights::Light, textures::TextureCoordinates, random_float, wavelength_weight};
+
+// Conservative bound on the relative error of n floating-point operations
+pub fn gamma(n: u32) -> Float {
+    let machine_epsilon = Float::EPSILON * 0.5;
+    return (n as Float * machine_epsilon) / (1.0 - n as Float * machine_epsilon);
+}
 
 pub struct Ray {
-    pub origin: Vector3<f64>,
-    pub direction: Unit<Vector3<f64>>,
+    pub origin: Vector3<Float>,
+    pub direction: Unit<Vector3<Float>>,
+    // Wavelength in nanometres carried by rays split up by a dispersive material
+    pub wavelength: Option<f64>,
+    // Ray cone used to pick texture detail, its width at the origin and its growth per unit of distance
+    pub cone_width: Float,
+    pub cone_spread: Float,
+    // Moment within the shutter interval the ray was sent at, from zero to one
+    pub time: Float,
 }
 
 impl Ray {
@@ -14,14 +27,18 @@ impl Ray {
         return Ray {
             origin: Vector3::zeros(),
             direction: Unit::new_normalize(Vector3::zeros()),
+            wavelength: None,
+            cone_width: 0.0,
+            cone_spread: 0.0,
+            time: 0.0,
         };
     }
 
-    pub fn intersect_bounds(self: &Self, bounds: &AABB) -> Option<f64> {
-        let inv_dir: Vector3<f64> = self.direction.apply_into(|x| 1.0 / x);
+    pub fn intersect_bounds(self: &Self, bounds: &AABB) -> Option<Float> {
+        let inv_dir: Vector3<Float> = self.direction.map(|x| 1.0 / x);
 
-        let t1: Vector3<f64> = inv_dir.component_mul(&(bounds.bmin - self.origin));
-        let t2: Vector3<f64> = inv_dir.component_mul(&(bounds.bmax - self.origin));
+        let t1: Vector3<Float> = inv_dir.component_mul(&(bounds.bmin - self.origin));
+        let t2: Vector3<Float> = inv_dir.component_mul(&(bounds.bmax - self.origin));
 
         let tmin = Vector3::new(t1.x.min(t2.x), t1.y.min(t2.y), t1.z.min(t2.z));
         let tmax = Vector3::new(t1.x.max(t2.x), t1.y.max(t2.y), t1.z.max(t2.z));
@@ -33,65 +50,196 @@ impl Ray {
         return Some(dmin);
     }
 
-    pub fn get_intersection_point(self: &Self, dist: f64) -> Vector3<f64> {
+    pub fn get_intersection_point(self: &Self, dist: Float) -> Vector3<Float> {
         return self.origin + self.direction.scale(dist);
     }
 
-    pub fn update_shadow(self: &mut Self, origin: Vector3<f64>, direction: Unit<Vector3<f64>>) {
+    // Moves a spawned ray origin along the geometric normal, just far enough to leave the error
+    // box around the intersection point, to the side of the surface the ray is heading towards.
+    pub fn offset_origin(point: &Vector3<Float>, point_error: &Vector3<Float>, normal: &Vector3<Float>, direction: &Unit<Vector3<Float>>) -> Vector3<Float> {
+        let distance = normal.abs().dot(point_error);
+        let mut offset = normal.scale(distance);
+        if direction.dot(normal) < 0.0 { offset = -offset; }
+
+        let mut origin = point + offset;
+        // Round away from the surface so the offset survives the addition above
+        for i in 0..3 {
+            if offset[i] > 0.0 { origin[i] = origin[i].next_up(); }
+            else if offset[i] < 0.0 { origin[i] = origin[i].next_down(); }
+        }
+        return origin;
+    }
+
+    pub fn update_shadow(self: &mut Self, origin: Vector3<Float>, direction: Unit<Vector3<Float>>) {
         self.origin    = origin;
         self.direction = direction;
     }
 
-    pub fn update_prim(&mut self, x: usize, y: usize, camera: &Camera, screen: &Screen) {
-        let w: f64 = screen.width as f64;
-        let h: f64 = screen.height as f64;
-        let u: f64 = (x as f64) / w;
-        let v: f64 = (y as f64) / h;
-        let point_on_screen: Vector3<f64>     = screen.p0 + u * (screen.p1 - screen.p0) + v * (screen.p2 - screen.p0);
-        let ray_direction: Unit<Vector3<f64>> = Unit::new_normalize(point_on_screen - camera.origin);
-        let ray_origin: Vector3<f64>          = camera.origin;
+    // Aims the ray through the pixel and returns whether the camera's projection covers it
+    pub fn update_prim(&mut self, x: usize, y: usize, camera: &Camera, screen: &Screen) -> bool {
+        let w: Float = screen.width as Float;
+        let h: Float = screen.height as Float;
+        let mut u: Float = (x as Float) / w;
+        let mut v: Float = (y as Float) / h;
+        let mut eye: Float = 0.0;
+        if let Some(stereo) = &camera.stereo {
+            let (side, eye_u, eye_v) = stereo.split(u, v);
+            eye = side;
+            u = eye_u;
+            v = eye_v;
+        }
+
+        let (mut ray_origin, mut ray_direction) = match camera.projection.generate(camera, screen, u, v) {
+            None => { return false; }
+            Some(ray) => ray,
+        };
+        if let Some(stereo) = &camera.stereo {
+            let (eye_origin, eye_direction) = stereo.eye_ray(&camera.projection, camera.axes(), eye, ray_origin, ray_direction);
+            ray_origin = eye_origin;
+            ray_direction = eye_direction;
+        }
+
+        // A thin lens starts the ray at a random point of the aperture, aimed so every ray through
+        // the pixel meets on the plane in focus
+        if camera.aperture > 0.0 && matches!(camera.projection, Projection::Perspective) {
+            let (right, up, forward) = camera.axes();
+            let focus_point = ray_origin + ray_direction.as_ref() * (camera.focus_distance / ray_direction.dot(&forward));
+            let lens = camera.sample_aperture(random_float(), random_float()) * camera.aperture;
+
+            ray_origin = ray_origin + right * lens.x + up * lens.y;
+            ray_direction = Unit::new_normalize(focus_point - ray_origin);
+        }
 
-        self.origin    = ray_origin;
-        self.direction = ray_direction;
+        // The camera moves along with the scene while the shutter is open
+        self.time = camera.shutter_open + (camera.shutter_close - camera.shutter_open) * random_float();
+        ray_origin = ray_origin + camera.motion * self.time;
+
+        self.origin     = ray_origin;
+        self.direction  = ray_direction;
+        self.wavelength = None;
+        let (cone_width, cone_spread) = camera.projection.pixel_cone(camera, screen);
+        self.cone_width = cone_width;
+        self.cone_spread = cone_spread;
+        return true;
     }
 
-    fn calculate_light_energy(scene: &Scene, shadow_ray: &mut Ray, intersection_point: &Vector3<f64>, normal: &Vector3<f64>) -> f64 {
-        let mut energy = 0.0;
-        for light in scene.lights.iter() {
-            let light_vector: Vector3<f64>    = light.origin - intersection_point;
-            let distance: f64                 = light_vector.magnitude() - (light_vector.magnitude() * EPSILON);
-            let direction: Unit<Vector3<f64>> = Unit::new_normalize(light.origin - intersection_point);
-            let origin: Vector3<f64>          = intersection_point + (direction.scale( EPSILON));
+    // Estimates the light reflected towards the viewer from one light, averaged over its stratified
+    // samples. The glossy lobe is only evaluated for delta lights, traced rays pick up the others.
+    fn sample_light(scene: &Scene, shadow_ray: &mut Ray, light: &dyn Light, intersection_point: &Vector3<Float>, point_error: &Vector3<Float>, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, bsdf: &dyn Bsdf, glossy: Option<&dyn Bsdf>) -> Color {
+        let samples = light.sample_count();
+        let glossy = glossy.filter(|_| light.is_delta());
+        let mut color = Color::black();
 
-            shadow_ray.update_shadow(origin, direction);
+        // Stratify the samples over a samples × samples grid on the light
+        for i in 0..samples {
+            for j in 0..samples {
+                let u = (i as Float + random_float()) / samples as Float;
+                let v = (j as Float + random_float()) / samples as Float;
 
-            if !PointLight::occluded(&scene, shadow_ray, distance) {
-                let dist_falloff = 1.0 / (distance * distance);
-                let angle_falloff = normal.dot(&direction);
-                energy +=  dist_falloff * angle_falloff * light.intensity;
+                let sample = match light.illuminate(intersection_point, u, v) {
+                    None => { continue; }
+                    Some(sample) => sample,
+                };
+
+                let angle_falloff = normal.dot(&sample.direction);
+                if angle_falloff <= 0.0 { continue; }
+
+                let origin: Vector3<Float> = Ray::offset_origin(intersection_point, point_error, normal, &sample.direction);
+                let distance: Float        = (sample.distance - (origin - intersection_point).magnitude()) * (1.0 - SHADOW_EPSILON);
+
+                shadow_ray.update_shadow(origin, sample.direction);
+
+                if !scene.occluded(shadow_ray, distance) {
+                    let mut reflectance = bsdf.eval(normal, outgoing, &sample.direction);
+                    if let Some(glossy) = glossy {
+                        reflectance += glossy.eval(normal, outgoing, &sample.direction);
+                    }
+                    color += sample.energy * reflectance * angle_falloff as f64;
+                }
             }
         }
+
+        return color * (1.0 / (samples * samples) as f64);
+    }
+
+    fn calculate_light_energy(scene: &Scene, shadow_ray: &mut Ray, intersection_point: &Vector3<Float>, point_error: &Vector3<Float>, normal: &Vector3<Float>, outgoing: &Unit<Vector3<Float>>, bsdf: &dyn Bsdf, glossy: Option<&dyn Bsdf>) -> Color {
+        let mut energy = Color::black();
+
+        match &scene.light_sampler {
+            Some(light_sampler) if light_sampler.samples < scene.lights.len() => {
+                // Pick a few lights by power and weigh them by how likely they were to be picked
+                for _ in 0..light_sampler.samples {
+                    let (index, probability) = light_sampler.sample(random_float());
+                    let light_energy = Ray::sample_light(scene, shadow_ray, scene.lights[index].as_ref(), intersection_point, point_error, normal, outgoing, bsdf, glossy);
+                    energy += light_energy * (1.0 / (probability * light_sampler.samples as f64));
+                }
+            }
+            _ => {
+                for light in scene.lights.iter() {
+                    energy += Ray::sample_light(scene, shadow_ray, light.as_ref(), intersection_point, point_error, normal, outgoing, bsdf, glossy);
+                }
+            }
+        }
+
+        if let Some(environment) = &scene.environment {
+            energy += Ray::sample_light(scene, shadow_ray, environment.as_ref(), intersection_point, point_error, normal, outgoing, bsdf, glossy);
+        }
+
         return energy;
     }
 
-    fn determine_diffuse_color(scene: &Scene, shadow_ray: &mut Ray, material: &Material, intersection_point: &Vector3<f64>, normal: &Vector3<f64>) -> Color {
-        let energy = Ray::calculate_light_energy(scene, shadow_ray, &intersection_point, &normal);
-        let color = material.diffuse_color * energy;
+    // Returns the light emitted by the nearest light surface in front of the given distance
+    fn intersect_lights(self: &Self, scene: &Scene, max_distance: Float) -> Option<Color> {
+        let mut nearest: Option<(Float, Color)> = None;
+        for light in scene.lights.iter() {
+            if let Some((distance, emission)) = light.intersect(self) {
+                let nearest_distance = nearest.map_or(max_distance, |(d, _)| d);
+                if distance < nearest_distance {
+                    nearest = Some((distance, emission));
+                }
+            }
+        }
+        return nearest.map(|(_, emission)| emission);
+    }
+
+    // Lambert diffuse with the Blinn-Phong highlight, lit by the lights only
+    fn determine_direct_color(scene: &Scene, shadow_ray: &mut Ray, material: &Material, intersection_point: &Vector3<Float>, point_error: &Vector3<Float>, normal: &Vector3<Float>, direction: &Unit<Vector3<Float>>, diffuse_cof: f64) -> Color {
+        let bsdf = Phong {
+            diffuse_color: material.diffuse_color * diffuse_cof,
+            specular_color: material.specular_color,
+            shininess: material.shininess,
+        };
+        return Ray::calculate_light_energy(scene, shadow_ray, intersection_point, point_error, normal, &-*direction, &bsdf, None);
+    }
+
+    // Diffuse lobe lit by the lights, and the GGX lobe by a single ray picked by importance
+    // sampling, which converges to glossy reflections as frames accumulate
+    fn determine_metallic_roughness_color(self: &mut Self, scene: &Scene, shadow_ray: &mut Ray, material: &Material, metallic_roughness: &MetallicRoughness, intersection_point: &Vector3<Float>, point_error: &Vector3<Float>, normal: &Vector3<Float>, direction: &Unit<Vector3<Float>>, depth: &u32) -> Color {
+        let (diffuse, specular) = metallic_roughness.lobes(material.diffuse_color);
+        let outgoing = -*direction;
+        let mut color = Ray::calculate_light_energy(scene, shadow_ray, intersection_point, point_error, normal, &outgoing, &diffuse, Some(&specular));
+
+        if let Some(sample) = specular.sample(normal, &outgoing, random_float(), random_float()) {
+            self.origin = Ray::offset_origin(intersection_point, point_error, normal, &sample.direction);
+            self.direction = sample.direction;
+            color += self.trace(scene, shadow_ray, depth + 1) * sample.weight;
+        }
         return color;
     }
 
-    fn determine_specular_color(self: &mut Self, scene: &Scene, shadow_ray: &mut Ray, intersection_point: &Vector3<f64>, normal: &Vector3<f64>, depth: &u32) -> Color {
-        let reflect_dir: Vector3<f64> = self.direction.into_inner() - normal.scale(2.0 * normal.dot(&self.direction));
-        self.origin = intersection_point + (reflect_dir.scale(EPSILON));
+    fn determine_specular_color(self: &mut Self, scene: &Scene, shadow_ray: &mut Ray, intersection_point: &Vector3<Float>, point_error: &Vector3<Float>, normal: &Vector3<Float>, direction: &Unit<Vector3<Float>>, depth: &u32) -> Color {
+        let reflect_dir: Vector3<Float> = direction.into_inner() - normal.scale(2.0 * normal.dot(direction));
         self.direction = Unit::new_normalize(reflect_dir);
+        self.origin = Ray::offset_origin(intersection_point, point_error, normal, &self.direction);
         return self.trace(scene, shadow_ray, depth + 1);
     }
 
-    fn get_refractive_direction(self: &Self, material: &Material, normal: &Vector3<f64>) -> Option<Unit<Vector3<f64>>> {
-        let mut cosi = clamp(self.direction.dot(normal),-1.0, 1.0);
+    // Returns the cosine of the incident angle, the indices of refraction on the incident and
+    // transmitted side and the normal facing the incident side
+    fn get_refraction_setup(direction: &Unit<Vector3<Float>>, material: &Material, normal: &Vector3<Float>, wavelength: Option<f64>) -> (Float, Float, Float, Vector3<Float>) {
+        let mut cosi = clamp(direction.dot(normal),-1.0, 1.0);
         let mut etai = 1.0;
-        let mut etat = material.refraction_index
-            .expect("No refraction index is defined for this material");
+        let mut etat = material.get_refraction_index(wavelength) as Float;
         let mut normal_refraction = *normal;
 
         if cosi < 0.0 { cosi = -cosi; }
@@ -100,53 +248,185 @@ impl Ray {
             std::mem::swap(&mut etai, &mut etat);
         }
 
+        return (cosi, etai, etat, normal_refraction);
+    }
+
+    // Returns None on total internal reflection
+    fn get_refractive_direction(direction: &Unit<Vector3<Float>>, material: &Material, normal: &Vector3<Float>, wavelength: Option<f64>) -> Option<Unit<Vector3<Float>>> {
+        let (cosi, etai, etat, normal_refraction) = Ray::get_refraction_setup(direction, material, normal, wavelength);
+
         let eta = etai / etat;
         let k = 1.0 - eta * eta * (1.0 - cosi * cosi);
         
         if k < 0.0 { return None; }
         
-        return Some(Unit::new_normalize(self.direction.scale(eta) + (eta * cosi - k.sqrt()) * normal_refraction));
+        return Some(Unit::new_normalize(direction.scale(eta) + (eta * cosi - k.sqrt()) * normal_refraction));
+    }
+
+    // Fraction of the light reflected by a dielectric interface, from the exact Fresnel equations
+    // for unpolarized light. Returns 1 on total internal reflection.
+    fn fresnel(direction: &Unit<Vector3<Float>>, material: &Material, normal: &Vector3<Float>, wavelength: Option<f64>) -> f64 {
+        let (cosi, etai, etat, _) = Ray::get_refraction_setup(direction, material, normal, wavelength);
+
+        let sint = etai / etat * (1.0 - cosi * cosi).max(0.0).sqrt();
+        if sint >= 1.0 { return 1.0; }
+
+        let cost = (1.0 - sint * sint).max(0.0).sqrt();
+        let rs = (etat * cosi - etai * cost) / (etat * cosi + etai * cost);
+        let rp = (etai * cosi - etat * cost) / (etai * cosi + etat * cost);
+        return ((rs * rs + rp * rp) / 2.0) as f64;
     }
 
-    fn determine_refractive_color(self: &mut Self, scene: &Scene, shadow_ray: &mut Ray, intersection_point: &Vector3<f64>, normal: &Vector3<f64>, material: &Material, depth: &u32) -> Color {
-        let refractive_direction = self.get_refractive_direction(material, normal)
-            .expect("No refractive direction could be calculated");
-        self.origin = intersection_point + (refractive_direction.scale(EPSILON));
+    fn determine_refractive_color(self: &mut Self, scene: &Scene, shadow_ray: &mut Ray, intersection_point: &Vector3<Float>, point_error: &Vector3<Float>, normal: &Vector3<Float>, refractive_direction: Unit<Vector3<Float>>, depth: &u32) -> Color {
+        self.origin = Ray::offset_origin(intersection_point, point_error, normal, &refractive_direction);
         self.direction = refractive_direction;
         return self.trace(scene, shadow_ray, depth + 1);
     }
 
+    // Splits the light between the reflected and the transmitted ray by the Fresnel term
+    fn determine_dielectric_color(self: &mut Self, scene: &Scene, shadow_ray: &mut Ray, intersection_point: &Vector3<Float>, point_error: &Vector3<Float>, normal: &Vector3<Float>, material: &Material, direction: &Unit<Vector3<Float>>, depth: &u32) -> Color {
+        let wavelength = self.wavelength;
+        let reflectance = Ray::fresnel(direction, material, normal, wavelength);
+        let mut color = Color::black();
+
+        if reflectance > EPSILON as f64 {
+            let reflected_color = self.determine_specular_color(scene, shadow_ray, intersection_point, point_error, normal, direction, depth);
+            color += reflected_color * reflectance;
+        }
+
+        if let Some(refractive_direction) = Ray::get_refractive_direction(direction, material, normal, wavelength) {
+            if reflectance < 1.0 - EPSILON as f64 {
+                let refracted_color = self.determine_refractive_color(scene, shadow_ray, intersection_point, point_error, normal, refractive_direction, depth);
+                color += refracted_color * (1.0 - reflectance);
+            }
+        }
+
+        return color;
+    }
+
+    // Traces the ray once per stratified wavelength and converts the spectral results back to RGB
+    fn trace_spectral(self: &mut Self, scene: &Scene, shadow_ray: &mut Ray, depth: u32) -> Color {
+        let origin = self.origin;
+        let direction = self.direction;
+        let cone_width = self.cone_width;
+        let samples = scene.spectral_samples;
+        let mut color = Color::black();
+
+        for i in 0..samples {
+            let u = (i as f64 + random_float() as f64) / samples as f64;
+            let wavelength = WAVELENGTH_MIN + u * (WAVELENGTH_MAX - WAVELENGTH_MIN);
+            self.origin = origin;
+            self.direction = direction;
+            self.cone_width = cone_width;
+            self.wavelength = Some(wavelength);
+            color += self.trace(scene, shadow_ray, depth) * wavelength_weight(wavelength);
+        }
+
+        self.wavelength = None;
+        return color * (1.0 / samples as f64);
+    }
+
+    // Frosted glass, traced with a single reflected or refracted ray through a sampled microfacet
+    fn determine_rough_dielectric_color(self: &mut Self, scene: &Scene, shadow_ray: &mut Ray, intersection_point: &Vector3<Float>, point_error: &Vector3<Float>, normal: &Vector3<Float>, material: &Material, direction: &Unit<Vector3<Float>>, depth: &u32) -> Color {
+        let bsdf = RoughDielectric {
+            eta: material.get_refraction_index(self.wavelength),
+            alpha: (material.refraction_roughness * material.refraction_roughness).max(0.001),
+        };
+        let outgoing = -*direction;
+
+        // Glass has no diffuse part, only the highlights of lights that rays cannot hit
+        let no_diffuse = Lambert { albedo: Color::black() };
+        let mut color = Ray::calculate_light_energy(scene, shadow_ray, intersection_point, point_error, normal, &outgoing, &no_diffuse, Some(&bsdf));
+
+        if let Some(sample) = bsdf.sample(normal, &outgoing, random_float(), random_float()) {
+            self.origin = Ray::offset_origin(intersection_point, point_error, normal, &sample.direction);
+            self.direction = sample.direction;
+            color += self.trace(scene, shadow_ray, depth + 1) * sample.weight;
+        }
+        return color;
+    }
+
     pub fn trace(self: &mut Self, scene: &Scene, shadow_ray: &mut Ray, depth: u32) -> Color {
         if depth > RECURSION_LIMIT { return Color::black(); }
 
-        let intersection = scene.bvh.pool[0].traverse(self, &scene.bvh, &scene.primitives);
-        
+        let intersection = scene.intersect(self);
+
+        let max_distance = intersection.map_or(Float::INFINITY, |(_, distance)| distance);
+        if let Some(emission) = self.intersect_lights(scene, max_distance) {
+            return emission;
+        }
+
         if let Some((primitive_index, distance)) = intersection {
             let primitive = &scene.primitives[primitive_index];
-            let intersection_point: Vector3<f64> = self.get_intersection_point(distance);
-            let normal = primitive.get_normal(&intersection_point);
-            let material = primitive.get_material(&scene.materials);
+            // Surface queries happen where the primitive is at the ray's time
+            let offset = primitive.get_offset(self.time);
+            let (local_point, point_error) = primitive.refine_intersection(&(self.get_intersection_point(distance) - offset));
+            let intersection_point = local_point + offset;
+            let normal = primitive.get_normal(&local_point);
+            // Shadow rays look for blockers at the same moment
+            shadow_ray.time = self.time;
+            let mut material = primitive.get_material(&scene.materials);
+            // Dispersive materials bend every wavelength differently, so split up white rays
+            if material.dispersion.is_some() && self.wavelength.is_none() && scene.spectral_samples > 0 {
+                return self.trace_spectral(scene, shadow_ray, depth);
+            }
+            // Spawning secondary rays overwrites this ray, so keep the incoming direction
+            let direction = self.direction;
+            // Secondary rays continue the cone from its width at the hit, ignoring surface curvature
+            self.cone_width += self.cone_spread * distance;
+
+            // Lighting uses the normal bent by normal and bump maps, spawned rays are still offset
+            // along it which is close enough to the surface's for gentle bumps
+            let mut shading_normal: Vector3<Float> = normal.into_inner();
+            if !material.textures.is_empty() {
+                let coordinates = TextureCoordinates::new(primitive.as_ref(), &local_point, &direction, self.cone_width);
+                material = material.apply_textures(&scene.textures, &coordinates);
+
+                if material.textures.perturbs_normal() {
+                    let (dpdu, dpdv) = primitive.get_tangents(&local_point);
+                    shading_normal = material.perturb_normal(&scene.textures, &coordinates, &normal, &dpdu, &dpdv);
+                }
+            }
             let diffuse_cof = 1.0 - material.specular_cof - material.refraction_cof;
-            let mut color = Color::black();
+            // Emitters only glow on the side their normal faces, as mesh lights only light that side
+            let mut color = if direction.dot(&normal) < 0.0 { material.emission } else { Color::black() };
 
-            if diffuse_cof > EPSILON {
-                let diffuse_color = Ray::determine_diffuse_color(scene, shadow_ray, &material, &intersection_point, &normal);
-                color += diffuse_color * diffuse_cof;
+            if let Some(metallic_roughness) = &material.metallic_roughness {
+                if diffuse_cof > EPSILON as f64 {
+                    let surface_color = self.determine_metallic_roughness_color(scene, shadow_ray, &material, metallic_roughness, &intersection_point, &point_error, &shading_normal, &direction, &depth);
+                    color += surface_color * diffuse_cof;
+                }
+            } else if diffuse_cof > EPSILON as f64 || material.shininess > 0.0 {
+                color += Ray::determine_direct_color(scene, shadow_ray, &material, &intersection_point, &point_error, &shading_normal, &direction, diffuse_cof.max(0.0));
             }
 
-            if material.specular_cof > EPSILON {
-                let specular_color = self.determine_specular_color(scene, shadow_ray, &intersection_point, &normal, &depth);
+            if material.specular_cof > EPSILON as f64 {
+                let specular_color = self.determine_specular_color(scene, shadow_ray, &intersection_point, &point_error, &shading_normal, &direction, &depth);
                 color += specular_color * material.specular_cof;
             }
 
-            if material.refraction_cof > EPSILON {
-                let refractive_color = self.determine_refractive_color(scene, shadow_ray, &intersection_point, &normal, &material, &depth);
-                color += refractive_color * material.refraction_cof;
+            if material.refraction_cof > EPSILON as f64 {
+                let dielectric_color = if material.refraction_roughness > 0.0 {
+                    self.determine_rough_dielectric_color(scene, shadow_ray, &intersection_point, &point_error, &shading_normal, &material, &direction, &depth)
+                } else {
+                    self.determine_dielectric_color(scene, shadow_ray, &intersection_point, &point_error, &shading_normal, &material, &direction, &depth)
+                };
+                color += dielectric_color * material.refraction_cof;
+            }
+
+            // A ray reaching the back of a surface has travelled through the material's inside
+            if let Some(absorption) = material.absorption {
+                if direction.dot(&normal) > 0.0 {
+                    color = color * absorption.transmittance(distance as f64);
+                }
             }
 
             return color;
         }
 
-        return Color::black();
+        return match &scene.environment {
+            None => Color::black(),
+            Some(environment) => environment.radiance(&self.direction),
+        };
     }
 }
\ No newline at end of file
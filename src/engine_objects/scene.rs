@@ -1,15 +1,56 @@
-use super::{Material, bvh::BVH, lights::PointLight, primitives::Primitive, ray::Ray};
+use crate::{EPSILON, Float};
+
+use super::{ALPHA_CUTOFF, Material, bvh::BVH, environments::Environment, lights::{Light, LightSampler}, primitives::Primitive, ray::Ray, textures::{Texture, TextureCoordinates}};
 
 pub struct Scene {
     pub bvh: BVH,
     pub primitives: Vec<Box<dyn Primitive>>,
-    pub lights: Vec<PointLight>,
+    pub lights: Vec<Box<dyn Light>>,
+    // Samples a subset of the lights per shading point, all lights are sampled when absent
+    pub light_sampler: Option<LightSampler>,
+    pub environment: Option<Box<dyn Environment>>,
     pub materials: Vec<Material>,
+    // Images and patterns referenced by index from the materials
+    pub textures: Vec<Box<dyn Texture>>,
+    // Wavelengths traced per ray through dispersive materials, dispersion is ignored when zero
+    pub spectral_samples: usize,
 }
 
 impl Scene {
-    pub fn _get_nearest_intersection(&self, ray: &Ray) -> Option<(&Box<dyn Primitive>, f64)> {
-        let mut nearest_intersection: Option<(&Box<dyn Primitive>, f64)> = None;
+    // Nearest hit of the ray that is not cut out, as the index of the primitive and its distance
+    pub fn intersect(self: &Self, ray: &Ray) -> Option<(usize, Float)> {
+        let accept = |primitive_index: usize, distance: Float| !self.is_cut_out(ray, primitive_index, distance);
+        return self.bvh.pool[0].traverse(ray, &self.bvh, &self.primitives, &accept);
+    }
+
+    // Whether anything that casts a shadow lies along the ray closer than the distance. Refractive
+    // materials let light through, as do cut out parts of surfaces.
+    pub fn occluded(self: &Self, shadow_ray: &Ray, dist: Float) -> bool {
+        let accept = |primitive_index: usize, distance: Float| {
+            let material = self.primitives[primitive_index].get_material(&self.materials);
+            return !(material.refraction_cof > EPSILON as f64) && !self.is_cut_out(shadow_ray, primitive_index, distance);
+        };
+        return self.bvh.pool[0].any_hit(shadow_ray, &self.bvh, &self.primitives, dist, &accept);
+    }
+
+    // Whether the ray's hit at the distance lies in a hole cut by the material's alpha. Only the
+    // nearest hit of a primitive is tested, so a cut out sphere also loses its far side.
+    pub fn is_cut_out(self: &Self, ray: &Ray, primitive_index: usize, distance: Float) -> bool {
+        let primitive = &self.primitives[primitive_index];
+        let material = primitive.get_material(&self.materials);
+        let mut alpha = material.alpha;
+
+        if let Some(index) = material.textures.alpha {
+            let point = ray.get_intersection_point(distance) - primitive.get_offset(ray.time);
+            let cone_width = ray.cone_width + ray.cone_spread * distance;
+            let coordinates = TextureCoordinates::new(primitive.as_ref(), &point, &ray.direction, cone_width);
+            alpha *= self.textures[index].evaluate(&coordinates).luminance();
+        }
+        return alpha < ALPHA_CUTOFF;
+    }
+
+    pub fn _get_nearest_intersection(&self, ray: &Ray) -> Option<(&Box<dyn Primitive>, Float)> {
+        let mut nearest_intersection: Option<(&Box<dyn Primitive>, Float)> = None;
         
         for primitive in &self.primitives {
             let intersection = primitive.intersect(&ray);
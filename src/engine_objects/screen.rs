@@ -1,25 +1,27 @@
 use nalgebra::Vector3;
 
+use crate::Float;
+
 use super::camera::Camera;
 
 pub struct Screen {
     pub width: u32,
     pub height: u32,
-    pub center: Vector3<f64>,
-    pub ratio: f64,
-    pub p0: Vector3<f64>,
-    pub p1: Vector3<f64>,
-    pub p2: Vector3<f64>,
+    pub center: Vector3<Float>,
+    pub ratio: Float,
+    pub p0: Vector3<Float>,
+    pub p1: Vector3<Float>,
+    pub p2: Vector3<Float>,
 }
 
 impl Screen {
     pub fn new(camera: &Camera, width: u32, height: u32) -> Screen {
-        let pi = std::f64::consts::PI;
+        let pi = std::f64::consts::PI as Float;
         let distance = 1.0 / (camera.fov / 2.0 * pi / 180.0).tan();
-        let ratio = width as f64 / height as f64;
-        let center: Vector3<f64> = camera.origin + distance * camera.forward; 
-        let r: Vector3<f64> = camera.up.cross(&camera.forward);
-        let u: Vector3<f64> = camera.up; 
+        let ratio = width as Float / height as Float;
+        let center: Vector3<Float> = camera.origin + distance * camera.forward; 
+        let r: Vector3<Float> = camera.up.cross(&camera.forward);
+        let u: Vector3<Float> = camera.up; 
 
         Screen {
             width,
@@ -33,12 +35,13 @@ impl Screen {
     }
 
     pub fn update_screen(self: &mut Self, camera: &Camera) {
-        let pi = std::f64::consts::PI;
+        let pi = std::f64::consts::PI as Float;
         let distance = 1.0 / (camera.fov / 2.0 * pi / 180.0).tan();
-        let center: Vector3<f64> = camera.origin + distance * camera.forward; 
-        let r: Vector3<f64> = camera.up.cross(&camera.forward);
-        let u: Vector3<f64> = camera.forward.cross(&r); 
+        let center: Vector3<Float> = camera.origin + distance * camera.forward; 
+        let r: Vector3<Float> = camera.up.cross(&camera.forward);
+        let u: Vector3<Float> = camera.forward.cross(&r); 
 
+        self.center = center;
         self.p0 = center - r * self.ratio + u;
         self.p1 = center + r * self.ratio + u;
         self.p2 = center - r * self.ratio - u;
@@ -0,0 +1,76 @@
+use std::sync::OnceLock;
+
+use super::Color;
+
+// Shortest and longest visible wavelengths in nanometres
+pub static WAVELENGTH_MIN: f64 = 380.0;
+pub static WAVELENGTH_MAX: f64 = 780.0;
+
+// CIE 1931 colour matching functions using the multi-lobe Gaussian fit from Wyman, Sloan and
+// Shirley, "Simple Analytic Approximations to the CIE XYZ Color Matching Functions" (2013)
+pub fn cie_xyz(wavelength: f64) -> (f64, f64, f64) {
+    let lobe = |x: f64, mean: f64, low: f64, high: f64| -> f64 {
+        let t = (x - mean) * if x < mean { low } else { high };
+        return (-0.5 * t * t).exp();
+    };
+
+    let x = 1.056 * lobe(wavelength, 599.8, 0.0264, 0.0323)
+        + 0.362 * lobe(wavelength, 442.0, 0.0624, 0.0374)
+        - 0.065 * lobe(wavelength, 501.1, 0.0490, 0.0382);
+    let y = 0.821 * lobe(wavelength, 568.8, 0.0213, 0.0247)
+        + 0.286 * lobe(wavelength, 530.9, 0.0613, 0.0322);
+    let z = 1.217 * lobe(wavelength, 437.0, 0.0845, 0.0278)
+        + 0.681 * lobe(wavelength, 459.0, 0.0385, 0.0725);
+    return (x, y, z);
+}
+
+// Spectral radiance of a black body at the given wavelength in nanometres, in W/(m²·sr·m)
+pub fn planck(wavelength: f64, temperature: f64) -> f64 {
+    let c = 299792458.0;
+    let h = 6.62607015e-34;
+    let kb = 1.380649e-23;
+    let lambda = wavelength * 1e-9;
+    return (2.0 * h * c * c) / (lambda.powi(5) * ((h * c / (lambda * kb * temperature)).exp() - 1.0));
+}
+
+// Converts CIE XYZ to linear sRGB
+pub fn xyz_to_color(x: f64, y: f64, z: f64) -> Color {
+    return Color {
+        red: 3.2406 * x - 1.5372 * y - 0.4986 * z,
+        green: -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        blue: 0.0557 * x - 0.2040 * y + 1.0570 * z,
+    };
+}
+
+// Linear sRGB response to light of a single wavelength, with out of gamut negatives clipped
+fn wavelength_to_color(wavelength: f64) -> Color {
+    let (x, y, z) = cie_xyz(wavelength);
+    let color = xyz_to_color(x, y, z);
+    return Color {
+        red: color.red.max(0.0),
+        green: color.green.max(0.0),
+        blue: color.blue.max(0.0),
+    };
+}
+
+// Weight of a wavelength drawn uniformly from the visible range. Each channel averages to one over
+// the range, so a flat spectrum of ones adds up to white.
+pub fn wavelength_weight(wavelength: f64) -> Color {
+    static NORMALIZATION: OnceLock<Color> = OnceLock::new();
+    let normalization = NORMALIZATION.get_or_init(|| {
+        let mut sum = Color::black();
+        let mut count = 0;
+        let mut wavelength = WAVELENGTH_MIN;
+        while wavelength <= WAVELENGTH_MAX {
+            sum += wavelength_to_color(wavelength);
+            count += 1;
+            wavelength += 1.0;
+        }
+        return Color {
+            red: count as f64 / sum.red,
+            green: count as f64 / sum.green,
+            blue: count as f64 / sum.blue,
+        };
+    });
+    return wavelength_to_color(wavelength) * *normalization;
+}
@@ -0,0 +1,73 @@
+use nalgebra::{Unit, Vector3};
+
+use crate::Float;
+
+use super::Projection;
+
+// Renders a left and a right eye image next to each other from one camera
+#[derive(Clone, Copy, Debug)]
+pub struct Stereo {
+    // Interpupillary distance between the two eyes
+    pub eye_distance: Float,
+    // Distance along the view direction of the plane where both eyes see the same image, objects in
+    // front of it appear to stick out of the display
+    pub convergence: Float,
+    pub layout: StereoLayout,
+}
+
+// Placement of the two eye images, each squeezed into half of the frame as 3D displays expect
+#[derive(Clone, Copy, Debug)]
+pub enum StereoLayout {
+    // Left eye on the left half
+    SideBySide,
+    // Left eye on the top half
+    TopBottom,
+}
+
+impl StereoLayout {
+    pub fn from_name(name: &str) -> Option<StereoLayout> {
+        return match name {
+            "side-by-side" => Some(StereoLayout::SideBySide),
+            "top-bottom" => Some(StereoLayout::TopBottom),
+            _ => None,
+        };
+    }
+}
+
+impl Stereo {
+    // Splits a frame position into the eye, -1 for left and 1 for right, and the position within
+    // that eye's image
+    pub fn split(self: &Self, u: Float, v: Float) -> (Float, Float, Float) {
+        return match self.layout {
+            StereoLayout::SideBySide => {
+                if u < 0.5 { (-1.0, u * 2.0, v) } else { (1.0, u * 2.0 - 1.0, v) }
+            }
+            StereoLayout::TopBottom => {
+                if v < 0.5 { (-1.0, u, v * 2.0) } else { (1.0, u, v * 2.0 - 1.0) }
+            }
+        };
+    }
+
+    // Moves a ray of the central camera to the eye. Planar projections shift the eyes sideways and
+    // aim at the same point on the convergence plane, giving an off-axis frustum. Panoramas use
+    // omnidirectional stereo, where the eyes sit on a circle and look along its tangents so every
+    // direction has the right parallax.
+    pub fn eye_ray(self: &Self, projection: &Projection, axes: (Vector3<Float>, Vector3<Float>, Vector3<Float>), eye: Float, origin: Vector3<Float>, direction: Unit<Vector3<Float>>) -> (Vector3<Float>, Unit<Vector3<Float>>) {
+        let (right, up, forward) = axes;
+        let half = self.eye_distance / 2.0 * eye;
+
+        return match projection {
+            Projection::Perspective | Projection::Orthographic { .. } => {
+                let converged = origin + direction.as_ref() * (self.convergence / direction.dot(&forward));
+                let eye_origin = origin + right * half;
+                (eye_origin, Unit::new_normalize(converged - eye_origin))
+            }
+            _ => {
+                // Looking straight up or down the circle shrinks to a point
+                let tangent: Vector3<Float> = up.cross(&direction);
+                if tangent.magnitude_squared() < 1e-12 { return (origin, direction); }
+                (origin + tangent.normalize() * half, direction)
+            }
+        };
+    }
+}
@@ -0,0 +1,23 @@
+use crate::engine_objects::Color;
+
+use super::{Texture, TextureCoordinates, mix};
+
+// Cubes of alternating colours filling space, `scale` cubes per unit of length
+pub struct CheckerTexture {
+    pub even: Color,
+    pub odd: Color,
+    pub scale: f64,
+}
+
+impl Texture for CheckerTexture {
+    fn evaluate(self: &Self, coordinates: &TextureCoordinates) -> Color {
+        let point = coordinates.point.map(|c| c as f64 * self.scale);
+        let parity = (point.x.floor() + point.y.floor() + point.z.floor()) as i64 & 1;
+        let color = if parity == 0 { self.even } else { self.odd };
+
+        // Fade to the average once a footprint covers several cubes
+        let cubes = coordinates.point_footprint as f64 * self.scale;
+        let fade = ((cubes - 0.5) / 1.5).max(0.0).min(1.0);
+        return mix(color, mix(self.even, self.odd, 0.5), fade);
+    }
+}
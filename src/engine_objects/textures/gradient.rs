@@ -0,0 +1,21 @@
+use nalgebra::Vector3;
+
+use crate::{Float, engine_objects::Color};
+
+use super::{Texture, TextureCoordinates, mix};
+
+// Linear blend from `start_color` at `start` to `end_color` at `end`, constant beyond both
+pub struct GradientTexture {
+    pub start: Vector3<Float>,
+    pub end: Vector3<Float>,
+    pub start_color: Color,
+    pub end_color: Color,
+}
+
+impl Texture for GradientTexture {
+    fn evaluate(self: &Self, coordinates: &TextureCoordinates) -> Color {
+        let axis: Vector3<Float> = self.end - self.start;
+        let t = (coordinates.point - self.start).dot(&axis) / axis.magnitude_squared();
+        return mix(self.start_color, self.end_color, (t as f64).max(0.0).min(1.0));
+    }
+}
@@ -0,0 +1,136 @@
+use std::{fs::File, io::{BufReader, Error, ErrorKind, Read, Result}};
+
+use crate::engine_objects::Color;
+
+// Reads a PNG, JPEG or binary/ASCII PPM image, returning its width, height and pixels in scanline
+// order. Colour images are stored in sRGB and are converted to linear values when `srgb` is set,
+// data such as roughness is stored linearly.
+pub fn load_image(path: &str, srgb: bool) -> Result<(usize, usize, Vec<Color>)> {
+    let (width, height, channels, bytes) = read_image(path)?;
+
+    let decode = |value: u8| -> f64 {
+        let value = value as f64 / 255.0;
+        if !srgb { return value; }
+        if value <= 0.04045 { return value / 12.92; }
+        return ((value + 0.055) / 1.055).powf(2.4);
+    };
+
+    // Grey images repeat their one channel, a trailing alpha channel is ignored
+    let mut pixels = Vec::with_capacity(width * height);
+    for pixel in bytes.chunks_exact(channels) {
+        let (r, g, b) = if channels < 3 { (pixel[0], pixel[0], pixel[0]) } else { (pixel[0], pixel[1], pixel[2]) };
+        pixels.push(Color { red: decode(r), green: decode(g), blue: decode(b) });
+    }
+    return Ok((width, height, pixels));
+}
+
+// Reads the opacity of an image as grey pixels, from its alpha channel or from the brightness of
+// images without one
+pub fn load_alpha(path: &str) -> Result<(usize, usize, Vec<Color>)> {
+    let (width, height, channels, bytes) = read_image(path)?;
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for pixel in bytes.chunks_exact(channels) {
+        let alpha = match channels {
+            2 | 4 => pixel[channels - 1] as f64 / 255.0,
+            1 => pixel[0] as f64 / 255.0,
+            _ => Color { red: pixel[0] as f64, green: pixel[1] as f64, blue: pixel[2] as f64 }.luminance() / 255.0,
+        };
+        pixels.push(Color { red: alpha, green: alpha, blue: alpha });
+    }
+    return Ok((width, height, pixels));
+}
+
+// Returns the width, height, number of channels and the interleaved bytes of an image
+fn read_image(path: &str) -> Result<(usize, usize, usize, Vec<u8>)> {
+    let extension = path.rsplit('.').next().unwrap_or("").to_lowercase();
+    return match extension.as_str() {
+        "png" => read_png(path),
+        "jpg" | "jpeg" => read_jpeg(path),
+        "ppm" => read_ppm(path),
+        _ => Err(Error::new(ErrorKind::InvalidInput, "Unsupported image format")),
+    };
+}
+
+fn read_png(path: &str) -> Result<(usize, usize, usize, Vec<u8>)> {
+    let invalid = |error: png::DecodingError| Error::new(ErrorKind::InvalidData, error.to_string());
+    let mut decoder = png::Decoder::new(File::open(path)?);
+    // Expand palettes and small bit depths and strip 16 bit channels down to bytes
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(invalid)?;
+    let mut bytes = vec![0u8; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut bytes).map_err(invalid)?;
+    bytes.truncate(info.buffer_size());
+
+    let channels = info.color_type.samples();
+    return Ok((info.width as usize, info.height as usize, channels, bytes));
+}
+
+fn read_jpeg(path: &str) -> Result<(usize, usize, usize, Vec<u8>)> {
+    let invalid = |error: jpeg_decoder::Error| Error::new(ErrorKind::InvalidData, error.to_string());
+    let mut decoder = jpeg_decoder::Decoder::new(BufReader::new(File::open(path)?));
+    let bytes = decoder.decode().map_err(invalid)?;
+    let info = decoder.info().ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing JPEG header"))?;
+
+    let channels = match info.pixel_format {
+        jpeg_decoder::PixelFormat::L8 => 1,
+        jpeg_decoder::PixelFormat::RGB24 => 3,
+        _ => { return Err(Error::new(ErrorKind::InvalidData, "Only greyscale and RGB JPEG images are supported")); }
+    };
+    return Ok((info.width as usize, info.height as usize, channels, bytes));
+}
+
+fn read_ppm(path: &str) -> Result<(usize, usize, usize, Vec<u8>)> {
+    let mut data = Vec::new();
+    BufReader::new(File::open(path)?).read_to_end(&mut data)?;
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
+
+    // The header is four whitespace separated tokens, with comments running to the end of a line
+    let mut position = 0;
+    let mut tokens: Vec<String> = Vec::new();
+    while tokens.len() < 4 {
+        let byte = *data.get(position).ok_or_else(|| invalid("PPM header ended early"))?;
+        if byte == b'#' {
+            while position < data.len() && data[position] != b'\n' { position += 1; }
+        } else if byte.is_ascii_whitespace() {
+            position += 1;
+        } else {
+            let start = position;
+            while position < data.len() && !data[position].is_ascii_whitespace() { position += 1; }
+            tokens.push(String::from_utf8_lossy(&data[start..position]).to_string());
+        }
+    }
+    // A single whitespace byte separates the header from binary pixel data
+    position += 1;
+
+    let parse = |token: &str| token.parse::<usize>().map_err(|_| invalid("Invalid PPM header"));
+    let width = parse(&tokens[1])?;
+    let height = parse(&tokens[2])?;
+    let max_value = parse(&tokens[3])?;
+    if max_value == 0 || max_value > 65535 { return Err(invalid("Invalid PPM maximum value")); }
+    let scale = |value: usize| -> u8 { (value.min(max_value) * 255 / max_value) as u8 };
+
+    let count = width * height * 3;
+    let bytes: Vec<u8> = match tokens[0].as_str() {
+        "P6" if max_value < 256 => {
+            let pixels = data.get(position..(position + count)).ok_or_else(|| invalid("PPM pixel data ended early"))?;
+            pixels.iter().map(|value| scale(*value as usize)).collect()
+        }
+        "P6" => {
+            let pixels = data.get(position..(position + count * 2)).ok_or_else(|| invalid("PPM pixel data ended early"))?;
+            pixels.chunks_exact(2).map(|pair| scale((pair[0] as usize) << 8 | pair[1] as usize)).collect()
+        }
+        "P3" => {
+            let text = String::from_utf8_lossy(&data[(position - 1)..]);
+            let values: Vec<u8> = text.split_whitespace()
+                .take(count)
+                .map(|token| parse(token).map(scale))
+                .collect::<Result<Vec<u8>>>()?;
+            if values.len() != count { return Err(invalid("PPM pixel data ended early")); }
+            values
+        }
+        _ => { return Err(invalid("Not a PPM image")); }
+    };
+
+    return Ok((width, height, 3, bytes));
+}
@@ -0,0 +1,132 @@
+use std::io::Result;
+
+use nalgebra::Vector2;
+
+use crate::{Float, engine_objects::Color};
+
+use super::{Texture, TextureCoordinates, load_alpha, load_image};
+
+// How texture coordinates outside [0, 1) are mapped back onto the image
+#[derive(Clone, Copy, Debug)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl WrapMode {
+    pub fn from_name(name: &str) -> Option<WrapMode> {
+        return match name {
+            "repeat" => Some(WrapMode::Repeat),
+            "clamp" => Some(WrapMode::Clamp),
+            "mirror" => Some(WrapMode::Mirror),
+            _ => None,
+        };
+    }
+
+    fn wrap(self: &Self, texel: i64, size: usize) -> usize {
+        let size = size as i64;
+        let wrapped = match self {
+            WrapMode::Repeat => texel.rem_euclid(size),
+            WrapMode::Clamp => texel.max(0).min(size - 1),
+            WrapMode::Mirror => {
+                let period = texel.rem_euclid(2 * size);
+                if period < size { period } else { 2 * size - 1 - period }
+            }
+        };
+        return wrapped as usize;
+    }
+}
+
+struct MipLevel {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl MipLevel {
+    // Averages blocks of 2 × 2 texels into a level of half the resolution
+    fn downsample(self: &Self) -> MipLevel {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = Color::black();
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)].iter() {
+                    let sx = (2 * x + dx).min(self.width - 1);
+                    let sy = (2 * y + dy).min(self.height - 1);
+                    sum += self.pixels[sx + sy * self.width];
+                }
+                pixels.push(sum * 0.25);
+            }
+        }
+        return MipLevel { width, height, pixels };
+    }
+}
+
+// Image sampled with bilinear filtering, blending between the two mipmap levels closest to the
+// size of the footprint
+pub struct ImageTexture {
+    levels: Vec<MipLevel>,
+    pub wrap: WrapMode,
+}
+
+impl ImageTexture {
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>, wrap: WrapMode) -> ImageTexture {
+        let mut levels = vec![MipLevel { width, height, pixels }];
+        loop {
+            let last = &levels[levels.len() - 1];
+            if last.width == 1 && last.height == 1 { break; }
+            let next = last.downsample();
+            levels.push(next);
+        }
+        return ImageTexture { levels, wrap };
+    }
+
+    pub fn load(path: &str, srgb: bool, wrap: WrapMode) -> Result<ImageTexture> {
+        let (width, height, pixels) = load_image(path, srgb)?;
+        return Ok(ImageTexture::new(width, height, pixels, wrap));
+    }
+
+    // Loads the image's opacity instead of its colour, for masking materials
+    pub fn load_alpha(path: &str, wrap: WrapMode) -> Result<ImageTexture> {
+        let (width, height, pixels) = load_alpha(path)?;
+        return Ok(ImageTexture::new(width, height, pixels, wrap));
+    }
+
+    fn bilinear(self: &Self, level: &MipLevel, uv: &Vector2<Float>) -> Color {
+        // Texel centres sit at half integer positions
+        let x = uv.x as f64 * level.width as f64 - 0.5;
+        let y = uv.y as f64 * level.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+
+        let texel = |dx: i64, dy: i64| -> Color {
+            let sx = self.wrap.wrap(x0 as i64 + dx, level.width);
+            let sy = self.wrap.wrap(y0 as i64 + dy, level.height);
+            return level.pixels[sx + sy * level.width];
+        };
+
+        return texel(0, 0) * ((1.0 - tx) * (1.0 - ty))
+            + texel(1, 0) * (tx * (1.0 - ty))
+            + texel(0, 1) * ((1.0 - tx) * ty)
+            + texel(1, 1) * (tx * ty);
+    }
+}
+
+impl Texture for ImageTexture {
+    fn evaluate(self: &Self, coordinates: &TextureCoordinates) -> Color {
+        // The level whose texels are as wide as the footprint
+        let uv = &coordinates.uv;
+        let base = &self.levels[0];
+        let texels = coordinates.uv_footprint as f64 * base.width.max(base.height) as f64;
+        let lod = texels.max(1.0).log2().min((self.levels.len() - 1) as f64);
+
+        let lower = lod.floor() as usize;
+        let blend = lod - lower as f64;
+        let color = self.bilinear(&self.levels[lower], uv);
+        if blend <= 0.0 || lower + 1 >= self.levels.len() { return color; }
+        return color * (1.0 - blend) + self.bilinear(&self.levels[lower + 1], uv) * blend;
+    }
+}
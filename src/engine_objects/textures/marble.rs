@@ -0,0 +1,28 @@
+use crate::engine_objects::Color;
+
+use super::{Texture, TextureCoordinates, mix, turbulence};
+
+// Veins along the x axis, bands of a sine wave pushed around by turbulence
+pub struct MarbleTexture {
+    pub base: Color,
+    pub vein: Color,
+    pub scale: f64,
+    // How far the turbulence bends the veins
+    pub distortion: f64,
+    pub octaves: u32,
+}
+
+impl Texture for MarbleTexture {
+    fn evaluate(self: &Self, coordinates: &TextureCoordinates) -> Color {
+        let point = coordinates.point.map(|c| c as f64 * self.scale);
+        let footprint = coordinates.point_footprint as f64 * self.scale;
+        let phase = point.x + self.distortion * turbulence(&point, self.octaves, footprint);
+        // Sharpen the bands so the veins are thin compared to the base
+        let vein = (1.0 - (0.5 + 0.5 * phase.sin())).powi(4);
+
+        // Fade to the bands' average of 35/128 as the footprint grows towards a whole period
+        let periods = footprint / (2.0 * std::f64::consts::PI);
+        let fade = ((periods - 0.25) / 0.75).max(0.0).min(1.0);
+        return mix(self.base, self.vein, vein + (35.0 / 128.0 - vein) * fade);
+    }
+}
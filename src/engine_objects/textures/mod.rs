@@ -0,0 +1,18 @@
+mod texture;
+pub use texture::*;
+mod image;
+pub use image::*;
+mod imagetexture;
+pub use imagetexture::*;
+mod noise;
+pub use noise::*;
+mod checker;
+pub use checker::*;
+mod noisetexture;
+pub use noisetexture::*;
+mod marble;
+pub use marble::*;
+mod wood;
+pub use wood::*;
+mod gradient;
+pub use gradient::*;
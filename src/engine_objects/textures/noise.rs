@@ -0,0 +1,91 @@
+use std::sync::OnceLock;
+
+use nalgebra::Vector3;
+
+// Ken Perlin's permutation scheme, with a table shuffled once from a fixed seed so patterns are
+// the same on every run
+fn permutation() -> &'static [usize; 512] {
+    static PERMUTATION: OnceLock<[usize; 512]> = OnceLock::new();
+    return PERMUTATION.get_or_init(|| {
+        let mut table: Vec<usize> = (0..256).collect();
+        let mut state: u32 = 0x9e3779b9;
+        for i in (1..256).rev() {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            table.swap(i, state as usize % (i + 1));
+        }
+
+        let mut permutation = [0; 512];
+        for i in 0..512 {
+            permutation[i] = table[i % 256];
+        }
+        return permutation;
+    });
+}
+
+fn fade(t: f64) -> f64 {
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    return a + t * (b - a);
+}
+
+// Dot product of the offset with one of twelve gradient directions picked by the hash
+fn gradient(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 { y } else if h == 12 || h == 14 { x } else { z };
+    return (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v });
+}
+
+// Improved Perlin noise, smooth and roughly in [-1, 1], zero at every integer lattice point
+pub fn perlin(point: &Vector3<f64>) -> f64 {
+    let p = permutation();
+    let cell = point.map(|c| c.floor());
+    let (xi, yi, zi) = ((cell.x as i64 & 255) as usize, (cell.y as i64 & 255) as usize, (cell.z as i64 & 255) as usize);
+    let (x, y, z) = (point.x - cell.x, point.y - cell.y, point.z - cell.z);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let a = p[xi] + yi;
+    let aa = p[a] + zi;
+    let ab = p[a + 1] + zi;
+    let b = p[xi + 1] + yi;
+    let ba = p[b] + zi;
+    let bb = p[b + 1] + zi;
+
+    return lerp(w,
+        lerp(v,
+            lerp(u, gradient(p[aa], x, y, z), gradient(p[ba], x - 1.0, y, z)),
+            lerp(u, gradient(p[ab], x, y - 1.0, z), gradient(p[bb], x - 1.0, y - 1.0, z))),
+        lerp(v,
+            lerp(u, gradient(p[aa + 1], x, y, z - 1.0), gradient(p[ba + 1], x - 1.0, y, z - 1.0)),
+            lerp(u, gradient(p[ab + 1], x, y - 1.0, z - 1.0), gradient(p[bb + 1], x - 1.0, y - 1.0, z - 1.0))));
+}
+
+// Sums octaves of noise at doubling frequencies and halving amplitudes, skipping octaves finer than
+// the footprint since they would only alias. `absolute` folds every octave, giving turbulence.
+fn octaves(point: &Vector3<f64>, octaves: u32, footprint: f64, absolute: bool) -> f64 {
+    let mut sum = 0.0;
+    let mut frequency = 1.0;
+    let mut amplitude = 0.5;
+    for _ in 0..octaves {
+        if frequency * footprint > 1.0 { break; }
+        let noise = perlin(&(point * frequency));
+        sum += amplitude * if absolute { noise.abs() } else { noise };
+        frequency *= 2.0;
+        amplitude *= 0.5;
+    }
+    return sum;
+}
+
+// Fractal Brownian motion, roughly in [-1, 1]
+pub fn fbm(point: &Vector3<f64>, count: u32, footprint: f64) -> f64 {
+    return octaves(point, count, footprint, false);
+}
+
+// Sum of the absolute octaves, roughly in [0, 1], with sharp creases where the noise crosses zero
+pub fn turbulence(point: &Vector3<f64>, count: u32, footprint: f64) -> f64 {
+    return octaves(point, count, footprint, true);
+}
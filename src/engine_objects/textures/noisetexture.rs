@@ -0,0 +1,26 @@
+use crate::engine_objects::Color;
+
+use super::{Texture, TextureCoordinates, fbm, mix, turbulence};
+
+// Blends between two colours by fractal noise, or by turbulence for a billowy look
+pub struct NoiseTexture {
+    pub low: Color,
+    pub high: Color,
+    // Frequency of the coarsest octave per unit of length
+    pub scale: f64,
+    pub octaves: u32,
+    pub turbulent: bool,
+}
+
+impl Texture for NoiseTexture {
+    fn evaluate(self: &Self, coordinates: &TextureCoordinates) -> Color {
+        let point = coordinates.point.map(|c| c as f64 * self.scale);
+        let footprint = coordinates.point_footprint as f64 * self.scale;
+        let t = if self.turbulent {
+            turbulence(&point, self.octaves, footprint)
+        } else {
+            0.5 + 0.5 * fbm(&point, self.octaves, footprint)
+        };
+        return mix(self.low, self.high, t.max(0.0).min(1.0));
+    }
+}
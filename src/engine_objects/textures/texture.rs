@@ -0,0 +1,38 @@
+use nalgebra::{Unit, Vector2, Vector3};
+
+use crate::{Float, engine_objects::{Color, primitives::Primitive}};
+
+// Where a texture is looked up. Both footprints are the width of the area the lookup should
+// average over, so distant and grazing surfaces do not alias.
+pub struct TextureCoordinates {
+    pub uv: Vector2<Float>,
+    // In texture coordinate units
+    pub uv_footprint: Float,
+    // Hit point in world space, for textures defined throughout space
+    pub point: Vector3<Float>,
+    // In world units
+    pub point_footprint: Float,
+}
+
+impl TextureCoordinates {
+    // Coordinates of a point on the primitive hit by a ray cone of the given width, whose footprint
+    // stretches along the surface as the ray hits it at a grazing angle
+    pub fn new(primitive: &dyn Primitive, point: &Vector3<Float>, direction: &Unit<Vector3<Float>>, cone_width: Float) -> TextureCoordinates {
+        let cosine = direction.dot(&primitive.get_normal(point)).abs().max(0.1);
+        return TextureCoordinates {
+            uv: primitive.get_uv(point),
+            uv_footprint: cone_width * primitive.get_uv_scale() / cosine,
+            point: *point,
+            point_footprint: cone_width / cosine,
+        };
+    }
+}
+
+pub trait Texture {
+    fn evaluate(self: &Self, coordinates: &TextureCoordinates) -> Color;
+}
+
+// Linear blend between two colours
+pub fn mix(a: Color, b: Color, t: f64) -> Color {
+    return a * (1.0 - t) + b * t;
+}
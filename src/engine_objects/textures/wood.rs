@@ -0,0 +1,27 @@
+use crate::engine_objects::Color;
+
+use super::{Texture, TextureCoordinates, fbm, mix};
+
+// Growth rings around the y axis, made irregular with a little noise
+pub struct WoodTexture {
+    pub light: Color,
+    pub dark: Color,
+    // Rings per unit of length
+    pub rings: f64,
+    pub distortion: f64,
+}
+
+impl Texture for WoodTexture {
+    fn evaluate(self: &Self, coordinates: &TextureCoordinates) -> Color {
+        let point = coordinates.point.map(|c| c as f64);
+        let footprint = coordinates.point_footprint as f64 * self.rings;
+        let radius = (point.x * point.x + point.z * point.z).sqrt() * self.rings;
+        let ring = radius + self.distortion * fbm(&(point * self.rings), 4, footprint);
+        // Early wood fades slowly into late wood, which ends sharply at the next ring
+        let t = ring - ring.floor();
+
+        // Fade to the rings' average of 1/3 as the footprint grows towards a whole ring
+        let fade = ((footprint - 0.25) / 0.75).max(0.0).min(1.0);
+        return mix(self.light, self.dark, t * t + (1.0 / 3.0 - t * t) * fade);
+    }
+}
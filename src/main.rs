@@ -1,27 +1,343 @@
 mod engine_objects;
-use engine_objects::{Camera, Color, Material, Ray, Scene, Screen, bvh::BVH, lights::PointLight, primitives::{Primitive, Sphere, Triangle}};
+use engine_objects::{Absorption, Camera, CameraPath, Color, Controls, Dispersion, KeyMap, Material, MaterialTextures, MetallicRoughness, Projection, Ray, Scene, Screen, Stereo, StereoLayout, bvh::BVH, environments::{ConstantEnvironment, Environment, EquirectangularEnvironment, GradientEnvironment, PreethamSky}, lights::{DirectionalLight, DiskLight, Light, LightSampler, MeshLight, PointLight, RectLight, SphereLight, SpotLight}, primitives::{Moving, Primitive, Sphere, Triangle}, textures::{CheckerTexture, GradientTexture, ImageTexture, MarbleTexture, NoiseTexture, Texture, WoodTexture, WrapMode}};
 
-use minifb::{Key, Window, WindowOptions};
-use nalgebra::Vector3;
+use minifb::{Key, MouseButton, MouseMode, Window, WindowOptions};
+use nalgebra::{Unit, Vector2, Vector3};
 
 const WIDTH: usize = 1600;
 const HEIGHT: usize = 900;
-pub static EPSILON: f64 = 0.0001;
+#[cfg(not(feature = "f32"))]
+pub type Float = f64;
+#[cfg(feature = "f32")]
+pub type Float = f32;
+pub static EPSILON: Float = 0.0001;
+pub static SHADOW_EPSILON: Float = 0.0001;
 pub static RECURSION_LIMIT: u32 = 16;
 
+// Picks the environment from the command line: `--environment <file.hdr>` for an image,
+// `--background <r> <g> <b>` for a constant colour, `--sky` for a daylight sky with its sun,
+// otherwise a sky gradient
+fn load_environment(args: &Vec<String>, lights: &mut Vec<Box<dyn Light>>) -> Box<dyn Environment> {
+    if let Some(index) = args.iter().position(|arg| arg == "--environment") {
+        let path = args.get(index + 1).expect("Missing path after --environment");
+        let environment = EquirectangularEnvironment::load(path, 1.0, 4)
+            .expect("Unable to load the environment map");
+        return Box::new(environment);
+    }
+
+    if let Some(index) = args.iter().position(|arg| arg == "--background") {
+        let channel = |offset: usize| -> f64 {
+            args.get(index + offset)
+                .and_then(|value| value.parse().ok())
+                .expect("Expected three numbers after --background")
+        };
+        return Box::new(
+            ConstantEnvironment {
+                color: Color { red: channel(1), green: channel(2), blue: channel(3) },
+                samples: 4,
+            }
+        );
+    }
+
+    if args.iter().any(|arg| arg == "--sky") {
+        let sun_direction = Unit::new_normalize(Vector3::new(-0.5, 0.6, 0.8));
+        let sky = PreethamSky::new(sun_direction, 3.0, Color { red: 0.3, green: 0.3, blue: 0.3 }, 0.05, 4);
+        lights.push(Box::new(sky.sun_light(6.0)));
+        return Box::new(sky);
+    }
+
+    return Box::new(
+        GradientEnvironment {
+            zenith: Color { red: 0.08, green: 0.12, blue: 0.25 },
+            horizon: Color { red: 0.25, green: 0.25, blue: 0.3 },
+            ground: Color { red: 0.05, green: 0.05, blue: 0.05 },
+            samples: 4,
+        }
+    );
+}
+
+// Sets up the camera from the command line: `--projection <name>` with `--fov <degrees>`, the thin
+// lens with `--aperture <radius>`, `--focus <distance>` and `--blades <count>` for a polygonal
+// bokeh, `--stereo <side-by-side|top-bottom>` with `--ipd <distance>` and
+// `--convergence <distance>`, and motion blur with `--shutter <open> <close>` and
+// `--camera-motion <x> <y> <z>`
+fn load_camera(args: &Vec<String>, camera: &mut Camera) {
+    let value = |flag: &str| -> Option<String> {
+        let index = args.iter().position(|arg| arg == flag)?;
+        return Some(args.get(index + 1).expect("Missing value after camera option").clone());
+    };
+
+    if let Some(name) = value("--projection") {
+        camera.projection = Projection::from_name(&name)
+            .expect("Expected perspective, orthographic, fisheye, fisheye-equisolid, equirectangular or cubemap after --projection");
+    }
+    if let Some(fov) = value("--fov") {
+        camera.fov = fov.parse().expect("Expected a number after --fov");
+    }
+
+    if let Some(layout) = value("--stereo") {
+        let layout = StereoLayout::from_name(&layout)
+            .expect("Expected side-by-side or top-bottom after --stereo");
+        let mut stereo = Stereo { eye_distance: 0.064, convergence: 5.0, layout };
+        if let Some(ipd) = value("--ipd") {
+            stereo.eye_distance = ipd.parse().expect("Expected a number after --ipd");
+        }
+        if let Some(convergence) = value("--convergence") {
+            stereo.convergence = convergence.parse().expect("Expected a number after --convergence");
+        }
+        camera.stereo = Some(stereo);
+    }
+    if let Some(index) = args.iter().position(|arg| arg == "--shutter") {
+        let time = |offset: usize| -> Float {
+            args.get(index + offset)
+                .and_then(|value| value.parse().ok())
+                .expect("Expected two numbers after --shutter")
+        };
+        camera.shutter_open = time(1);
+        camera.shutter_close = time(2);
+    }
+    if let Some(index) = args.iter().position(|arg| arg == "--camera-motion") {
+        let coordinate = |offset: usize| -> Float {
+            args.get(index + offset)
+                .and_then(|value| value.parse().ok())
+                .expect("Expected three numbers after --camera-motion")
+        };
+        camera.motion = Vector3::new(coordinate(1), coordinate(2), coordinate(3));
+    }
+    if let Some(aperture) = value("--aperture") {
+        camera.aperture = aperture.parse().expect("Expected a number after --aperture");
+    }
+    if let Some(focus) = value("--focus") {
+        camera.focus_distance = focus.parse().expect("Expected a number after --focus");
+    }
+    if let Some(blades) = value("--blades") {
+        camera.aperture_blades = blades.parse().expect("Expected a whole number after --blades");
+    }
+}
+
+// Procedural floor patterns selectable with `--procedural <name>`
+fn procedural_texture(name: &str) -> Option<Box<dyn Texture>> {
+    let light = Color { red: 0.9, green: 0.88, blue: 0.82 };
+    let dark = Color { red: 0.25, green: 0.25, blue: 0.28 };
+
+    return match name {
+        "checker" => Some(Box::new(CheckerTexture { even: light, odd: dark, scale: 0.5 })),
+        "noise" => Some(Box::new(NoiseTexture { low: dark, high: light, scale: 0.8, octaves: 6, turbulent: false })),
+        "turbulence" => Some(Box::new(NoiseTexture { low: dark, high: light, scale: 0.8, octaves: 6, turbulent: true })),
+        "marble" => Some(Box::new(MarbleTexture { base: light, vein: dark, scale: 1.5, distortion: 6.0, octaves: 6 })),
+        "wood" => Some(Box::new(WoodTexture {
+            light: Color { red: 0.75, green: 0.52, blue: 0.3 },
+            dark: Color { red: 0.35, green: 0.2, blue: 0.1 },
+            rings: 1.0,
+            distortion: 0.6,
+        })),
+        "gradient" => Some(Box::new(GradientTexture {
+            start: Vector3::new(0.0, -2.0, 0.0),
+            end: Vector3::new(0.0, -2.0, 20.0),
+            start_color: light,
+            end_color: Color { red: 0.2, green: 0.3, blue: 0.6 },
+        })),
+        _ => None,
+    };
+}
+
+// Loads the image given by `--texture <file>` onto the floor, tiled with the mode from
+// `--texture-wrap <repeat|clamp|mirror>`, or one of the patterns from `--procedural <name>`. The
+// floor's relief comes from a tangent space `--normal-map <file>` or a `--bump <name>` pattern.
+fn load_textures(args: &Vec<String>, materials: &mut Vec<Material>) -> Vec<Box<dyn Texture>> {
+    let mut textures: Vec<Box<dyn Texture>> = Vec::new();
+
+    if let Some(index) = args.iter().position(|arg| arg == "--texture") {
+        let path = args.get(index + 1).expect("Missing path after --texture");
+        let wrap = match args.iter().position(|arg| arg == "--texture-wrap") {
+            None => WrapMode::Repeat,
+            Some(index) => args.get(index + 1)
+                .and_then(|name| WrapMode::from_name(name))
+                .expect("Expected repeat, clamp or mirror after --texture-wrap"),
+        };
+        let texture = ImageTexture::load(path, true, wrap)
+            .expect("Unable to load the texture");
+        textures.push(Box::new(texture));
+        materials[3].textures.albedo = Some(textures.len() - 1);
+    } else if let Some(index) = args.iter().position(|arg| arg == "--procedural") {
+        let texture = args.get(index + 1)
+            .and_then(|name| procedural_texture(name))
+            .expect("Expected checker, noise, turbulence, marble, wood or gradient after --procedural");
+        textures.push(texture);
+        materials[3].textures.albedo = Some(textures.len() - 1);
+    }
+
+    if let Some(index) = args.iter().position(|arg| arg == "--normal-map") {
+        let path = args.get(index + 1).expect("Missing path after --normal-map");
+        // Normal maps hold directions, not colours, so they are read without the sRGB curve
+        let texture = ImageTexture::load(path, false, WrapMode::Repeat)
+            .expect("Unable to load the normal map");
+        textures.push(Box::new(texture));
+        materials[3].textures.normal = Some(textures.len() - 1);
+    }
+
+    if let Some(index) = args.iter().position(|arg| arg == "--bump") {
+        let texture = args.get(index + 1)
+            .and_then(|name| procedural_texture(name))
+            .expect("Expected checker, noise, turbulence, marble, wood or gradient after --bump");
+        textures.push(texture);
+        materials[3].textures.bump = Some(textures.len() - 1);
+        materials[3].textures.bump_height = 0.3;
+    }
+
+    // Cuts holes in the floor, from a procedural pattern or an image's alpha channel
+    if let Some(index) = args.iter().position(|arg| arg == "--alpha-mask") {
+        let name = args.get(index + 1).expect("Missing texture after --alpha-mask");
+        let texture = match procedural_texture(name) {
+            Some(texture) => texture,
+            None => Box::new(ImageTexture::load_alpha(name, WrapMode::Repeat)
+                .expect("Unable to load the alpha mask")),
+        };
+        textures.push(texture);
+        materials[3].textures.alpha = Some(textures.len() - 1);
+    }
+
+    return textures;
+}
+
+// Builds a closed triangular prism by extruding the triangle a, b, c along the given length, with
+// every face pointing outwards so refraction knows which side is inside
+fn create_prism(a: Vector3<Float>, b: Vector3<Float>, c: Vector3<Float>, length: Vector3<Float>, material_index: usize) -> Vec<Box<dyn Primitive>> {
+    let (a2, b2, c2) = (a + length, b + length, c + length);
+    let center = (a + b + c) / 3.0 + length / 2.0;
+    let faces = [
+        (a, b, c), (a2, c2, b2),
+        (a, b, b2), (a, b2, a2),
+        (b, c, c2), (b, c2, b2),
+        (c, a, a2), (c, a2, c2),
+    ];
+
+    let mut triangles: Vec<Box<dyn Primitive>> = Vec::new();
+    for (v0, v1, v2) in faces.iter() {
+        let normal = (v1 - v0).cross(&(v2 - v0));
+        let flip_normal = normal.dot(&((v0 + v1 + v2) / 3.0 - center)) < 0.0;
+        triangles.push(Box::new(Triangle::create_triangle(*v0, *v1, *v2, flip_normal, material_index)));
+    }
+    return triangles;
+}
+
+// Writes 0RGB pixels, as shown in the window, to a binary PPM image
+fn save_ppm(path: &str, width: usize, height: usize, pixels: &Vec<u32>) -> std::io::Result<()> {
+    let mut bytes = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for pixel in pixels {
+        bytes.push((pixel >> 16) as u8);
+        bytes.push((pixel >> 8) as u8);
+        bytes.push(*pixel as u8);
+    }
+    return std::fs::write(path, bytes);
+}
+
+// Renders the keyframed camera path from `--render-path <file>` without opening a window, as
+// `--frames <count>` PPM images named `<prefix>_0000.ppm` onwards after `--output <prefix>`, each
+// averaging `--samples <count>` passes
+fn render_path(args: &Vec<String>, path_file: &str, scene: &Scene, camera: &mut Camera, screen: &mut Screen) {
+    let value = |flag: &str, default: usize| -> Option<usize> {
+        return match args.iter().position(|arg| arg == flag) {
+            None => Some(default),
+            Some(index) => args.get(index + 1).and_then(|value| value.parse().ok()),
+        };
+    };
+    let frames = match value("--frames", 60) {
+        Some(frames) => frames.max(1),
+        None => {
+            println!("Expected a whole number after --frames");
+            return;
+        }
+    };
+    let samples = match value("--samples", 16) {
+        Some(samples) => samples.max(1),
+        None => {
+            println!("Expected a whole number after --samples");
+            return;
+        }
+    };
+    let prefix = match args.iter().position(|arg| arg == "--output") {
+        None => "frame",
+        Some(index) => match args.get(index + 1) {
+            Some(prefix) => prefix,
+            None => {
+                println!("Missing prefix after --output");
+                return;
+            }
+        },
+    };
+
+    let path = match CameraPath::load(path_file) {
+        Ok(path) => path,
+        Err(error) => {
+            println!("Unable to load the camera path {}: {}", path_file, error);
+            return;
+        }
+    };
+    let (start, end) = (path.start_time(), path.end_time());
+    let mut prim_ray = Ray::new();
+    let mut shadow_ray = Ray::new();
+    let mut buffer: Vec<u32> = vec![0; WIDTH * HEIGHT];
+
+    for frame in 0..frames {
+        let t = if frames > 1 { frame as Float / (frames - 1) as Float } else { 0.0 };
+        camera.set_pose(&path.evaluate(start + (end - start) * t));
+        screen.update_screen(camera);
+
+        profile!(
+            "Rendering frame" {
+                for y in 0..HEIGHT {
+                    for x in 0..WIDTH {
+                        let mut color = Color::black();
+                        for _ in 0..samples {
+                            if prim_ray.update_prim(x, y, camera, screen) {
+                                color += prim_ray.trace(scene, &mut shadow_ray, 0);
+                            }
+                        }
+                        buffer[x + y * WIDTH] = camera.expose(color * (1.0 / samples as f64)).to_u32();
+                    }
+                }
+            }
+        );
+
+        let file = format!("{}_{:04}.ppm", prefix, frame);
+        if let Err(error) = save_ppm(&file, WIDTH, HEIGHT, &buffer) {
+            println!("Unable to write {}: {}", file, error);
+            return;
+        }
+        println!("Wrote {}", file);
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
     let mut buffer: Vec<u32> = vec![0; WIDTH * HEIGHT];
+    // Sum of the frames rendered since the camera last moved, averaged for display
+    let mut accumulator: Vec<Color> = vec![Color::black(); WIDTH * HEIGHT];
+    let mut frame_count: u32 = 0;
+    // Time the previous frame was shown, to move the camera by the time that passed since
+    let mut frame_start = std::time::Instant::now();
 
     let mut camera: Camera = Camera {
         origin: Vector3::new(0.0, 0.0, 0.0),
         forward: Vector3::new(0.0, 0.0, 1.0),
         up: Vector3::new(0.0, 1.0, 0.0),
         fov: 90.0,
+        exposure: 0.0,
+        projection: Projection::Perspective,
+        stereo: None,
+        aperture: 0.0,
+        focus_distance: 3.0,
+        aperture_blades: 0,
+        shutter_open: 0.0,
+        shutter_close: 0.0,
+        motion: Vector3::zeros(),
     };
+    load_camera(&args, &mut camera);
 
     let mut screen: Screen = Screen::new(&camera, WIDTH as u32, HEIGHT as u32);
 
-    let primitives: Vec<Box<dyn Primitive>> = vec![
+    let mut primitives: Vec<Box<dyn Primitive>> = vec![
         Box::new(
             Sphere {
                 origin: Vector3::new(0.0, 0.0, 5.0),
@@ -30,10 +346,16 @@ fn main() {
             }
         ),
         Box::new(
-            Sphere {
-                origin: Vector3::new(4.0, 0.0, 5.0),
-                radius2: 3.0,
-                material_index: 1,
+            Moving {
+                primitive: Box::new(
+                    Sphere {
+                        origin: Vector3::new(4.0, 0.0, 5.0),
+                        radius2: 3.0,
+                        material_index: 1,
+                    }
+                ),
+                start: Vector3::zeros(),
+                end: Vector3::new(0.0, 1.0, 0.0),
             }
         ),
         Box::new(
@@ -43,6 +365,20 @@ fn main() {
                 material_index: 2,
             }
         ),
+        Box::new(
+            Sphere {
+                origin: Vector3::new(-2.5, -0.9, 3.0),
+                radius2: 1.21,
+                material_index: 5,
+            }
+        ),
+        Box::new(
+            Sphere {
+                origin: Vector3::new(-0.6, -1.49, 2.2),
+                radius2: 0.25,
+                material_index: 7,
+            }
+        ),
         Box::new(
             Triangle::create_triangle(
                 Vector3::new(0.0, -2.0, 10.0), 
@@ -51,43 +387,261 @@ fn main() {
                 true, 
                 2,
             )
-        )
+        ),
+        Box::new(
+            Triangle::create_textured_triangle(
+                Vector3::new(-20.0, -2.0, -5.0),
+                Vector3::new(20.0, -2.0, -5.0),
+                Vector3::new(20.0, -2.0, 30.0),
+                Vector2::new(0.0, 0.0),
+                Vector2::new(20.0, 0.0),
+                Vector2::new(20.0, 17.5),
+                true,
+                3,
+            )
+        ),
+        Box::new(
+            Triangle::create_textured_triangle(
+                Vector3::new(-20.0, -2.0, -5.0),
+                Vector3::new(20.0, -2.0, 30.0),
+                Vector3::new(-20.0, -2.0, 30.0),
+                Vector2::new(0.0, 0.0),
+                Vector2::new(20.0, 17.5),
+                Vector2::new(0.0, 17.5),
+                true,
+                3,
+            )
+        ),
+        Box::new(
+            Triangle::create_triangle(
+                Vector3::new(-7.0, -2.0, 12.0),
+                Vector3::new(-3.0, -2.0, 14.0),
+                Vector3::new(-3.0, 2.0, 14.0),
+                false,
+                4,
+            )
+        ),
+        Box::new(
+            Triangle::create_triangle(
+                Vector3::new(-7.0, -2.0, 12.0),
+                Vector3::new(-3.0, 2.0, 14.0),
+                Vector3::new(-7.0, 2.0, 12.0),
+                false,
+                4,
+            )
+        ),
+    ];
+    primitives.extend(create_prism(
+        Vector3::new(1.4, -2.0, 2.4),
+        Vector3::new(2.4, -2.0, 2.4),
+        Vector3::new(1.9, -2.0, 3.2),
+        Vector3::new(0.0, 1.6, 0.0),
+        6,
+    ));
+
+    let mut materials: Vec<Material> = vec![
+        Material {
+            diffuse_color: Color::red(),
+            refraction_index: Some(1.5),
+            refraction_cof: 0.0,
+            refraction_roughness: 0.0,
+            specular_color: Color::white() * 0.4,
+            shininess: 64.0,
+            specular_cof: 0.2,
+            emission: Color::black(),
+            absorption: None,
+            metallic_roughness: None,
+            dispersion: None,
+            textures: MaterialTextures::none(),
+            alpha: 1.0,
+        },
+        Material {
+            diffuse_color: Color::green(),
+            refraction_index: None,
+            refraction_cof: 0.0,
+            refraction_roughness: 0.0,
+            specular_color: Color::white() * 0.25,
+            shininess: 16.0,
+            specular_cof: 0.0,
+            emission: Color::black(),
+            absorption: None,
+            metallic_roughness: None,
+            dispersion: None,
+            textures: MaterialTextures::none(),
+            alpha: 1.0,
+        },
+        Material {
+            diffuse_color: Color::blue(),
+            refraction_index: None,
+            refraction_cof: 0.0,
+            refraction_roughness: 0.0,
+            specular_color: Color::black(),
+            shininess: 0.0,
+            specular_cof: 0.0,
+            emission: Color::black(),
+            absorption: None,
+            metallic_roughness: Some(MetallicRoughness { metallic: 1.0, roughness: 0.3 }),
+            dispersion: None,
+            textures: MaterialTextures::none(),
+            alpha: 1.0,
+        },
+        Material {
+            diffuse_color: Color::white(),
+            refraction_index: None,
+            refraction_cof: 0.0,
+            refraction_roughness: 0.0,
+            specular_color: Color::black(),
+            shininess: 0.0,
+            specular_cof: 0.0,
+            emission: Color::black(),
+            absorption: None,
+            metallic_roughness: None,
+            dispersion: None,
+            textures: MaterialTextures::none(),
+            alpha: 1.0,
+        },
+        Material {
+            diffuse_color: Color::black(),
+            refraction_index: None,
+            refraction_cof: 0.0,
+            refraction_roughness: 0.0,
+            specular_color: Color::black(),
+            shininess: 0.0,
+            specular_cof: 0.0,
+            emission: Color { red: 12.0, green: 5.0, blue: 1.2 },
+            absorption: None,
+            metallic_roughness: None,
+            dispersion: None,
+            textures: MaterialTextures::none(),
+            alpha: 1.0,
+        },
+        Material {
+            diffuse_color: Color::black(),
+            refraction_index: Some(1.5),
+            refraction_cof: 1.0,
+            refraction_roughness: 0.0,
+            specular_color: Color::black(),
+            shininess: 0.0,
+            specular_cof: 0.0,
+            emission: Color::black(),
+            absorption: Some(Absorption { color: Color { red: 0.45, green: 0.85, blue: 0.6 }, density: 1.0 }),
+            metallic_roughness: None,
+            dispersion: Some(Dispersion::Cauchy { a: 1.5, b: 0.03 }),
+            textures: MaterialTextures::none(),
+            alpha: 1.0,
+        },
+        Material {
+            diffuse_color: Color::black(),
+            refraction_index: Some(2.42),
+            refraction_cof: 1.0,
+            refraction_roughness: 0.0,
+            specular_color: Color::black(),
+            shininess: 0.0,
+            specular_cof: 0.0,
+            emission: Color::black(),
+            absorption: None,
+            metallic_roughness: None,
+            dispersion: Some(Dispersion::diamond()),
+            textures: MaterialTextures::none(),
+            alpha: 1.0,
+        },
+        Material {
+            diffuse_color: Color::black(),
+            refraction_index: Some(1.5),
+            refraction_cof: 1.0,
+            refraction_roughness: 0.3,
+            specular_color: Color::black(),
+            shininess: 0.0,
+            specular_cof: 0.0,
+            emission: Color::black(),
+            absorption: None,
+            metallic_roughness: None,
+            dispersion: None,
+            textures: MaterialTextures::none(),
+            alpha: 1.0,
+        },
     ];
 
+    let mut lights: Vec<Box<dyn Light>> = vec![
+        Box::new(
+            PointLight {
+                origin: Vector3::new(0.0, 0.0, 0.0),
+                power: Color::from_temperature(2700.0) * 1000.0,
+            }
+        ),
+        Box::new(
+            DirectionalLight {
+                direction: Unit::new_normalize(Vector3::new(-1.0, -1.0, 1.0)),
+                irradiance: Color::from_temperature(4500.0) * 1.0,
+            }
+        ),
+        Box::new(
+            SpotLight {
+                origin: Vector3::new(4.0, 6.0, 2.0),
+                direction: Unit::new_normalize(Vector3::new(0.0, -6.0, 3.0)),
+                power: Color::white() * 25.0,
+                cone_angle: 25.0,
+                penumbra_angle: 10.0,
+            }
+        ),
+        Box::new(
+            RectLight {
+                center: Vector3::new(0.0, 7.0, 6.0),
+                edge_u: Vector3::new(1.5, 0.0, 0.0),
+                edge_v: Vector3::new(0.0, 0.0, 1.0),
+                radiance: Color::white() * 10.0,
+                samples: 4,
+            }
+        ),
+        Box::new(
+            DiskLight {
+                center: Vector3::new(-4.0, 3.0, 3.0),
+                normal: Unit::new_normalize(Vector3::new(4.0, -3.0, 2.0)),
+                radius: 0.75,
+                radiance: Color { red: 0.6, green: 0.7, blue: 1.0 } * 25.0,
+                samples: 4,
+            }
+        ),
+        Box::new(
+            SphereLight {
+                origin: Vector3::new(6.0, 4.0, 2.0),
+                radius: 0.5,
+                radiance: Color::from_temperature(3000.0) * 100.0,
+                samples: 4,
+            }
+        ),
+    ];
+    if let Some(mesh_light) = MeshLight::new(&primitives, &materials, 4) {
+        lights.push(Box::new(mesh_light));
+    }
+
+    let environment = load_environment(&args, &mut lights);
+    let textures = load_textures(&args, &mut materials);
+
     let mut bvh = BVH::new(0, primitives.len());
     bvh.build(&primitives);
 
+    let light_sampler = LightSampler::new(&lights, &bvh.pool[0].bounds, 3);
+
     let scene: Scene = Scene {
         bvh,
         primitives,
-        lights: vec![
-            PointLight {
-                origin: Vector3::new(0.0, 0.0, 0.0),
-                intensity: 30.0,
-            },
-        ],
-        materials: vec![
-            Material {
-                diffuse_color: Color::red(),
-                refraction_index: Some(1.5),
-                refraction_cof: 0.0,
-                specular_cof: 0.2,
-            },
-            Material {
-                diffuse_color: Color::green(),
-                refraction_index: None,
-                refraction_cof: 0.0,
-                specular_cof: 0.0,
-            },
-            Material {
-                diffuse_color: Color::blue(),
-                refraction_index: None,
-                refraction_cof: 0.0,
-                specular_cof: 0.0,
-            },
-        ]
+        lights,
+        light_sampler: Some(light_sampler),
+        environment: Some(environment),
+        materials,
+        textures,
+        spectral_samples: 8,
     };
 
+    if let Some(index) = args.iter().position(|arg| arg == "--render-path") {
+        match args.get(index + 1) {
+            Some(path_file) => render_path(&args, path_file, &scene, &mut camera, &mut screen),
+            None => println!("Missing path after --render-path"),
+        }
+        return;
+    }
+
     let mut prim_ray = Ray::new();
     let mut shadow_ray = Ray::new();
 
@@ -101,21 +655,66 @@ fn main() {
 
     window.limit_update_rate(Some(std::time::Duration::from_micros(16600)));
 
+    let key_map = match args.iter().position(|arg| arg == "--keys") {
+        None => KeyMap::default(),
+        Some(index) => KeyMap::load(args.get(index + 1).expect("Missing path after --keys"))
+            .expect("Unable to load the key map"),
+    };
+    // Bookmarks are kept in `--bookmarks <file>`, which need not exist yet
+    let bookmark_file = match args.iter().position(|arg| arg == "--bookmarks") {
+        None => String::from("bookmarks.txt"),
+        Some(index) => args.get(index + 1).expect("Missing path after --bookmarks").clone(),
+    };
+    let bookmarks = match CameraPath::load(&bookmark_file) {
+        Ok(bookmarks) => bookmarks,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => CameraPath { keyframes: Vec::new() },
+        Err(error) => panic!("Unable to load the bookmarks: {}", error),
+    };
+    let mut controls = Controls::new(key_map, bookmarks, bookmark_file);
+
     while window.is_open() && !window.is_key_down(Key::Escape) {
+        frame_count += 1;
+        let frame_weight = 1.0 / frame_count as f64;
+
         profile!(
             "Rendering" {
                 for y in 0..HEIGHT {
                     for x in 0..WIDTH {
-                        prim_ray.update_prim(x, y, &camera, &screen);
-                        let color = prim_ray.trace(&scene, &mut shadow_ray, 0);
+                        let color = if prim_ray.update_prim(x, y, &camera, &screen) {
+                            prim_ray.trace(&scene, &mut shadow_ray, 0)
+                        } else {
+                            Color::black()
+                        };
                         let index = x + y * WIDTH;
-                        buffer[index] = color.to_u32();
+                        accumulator[index] += color;
+                        buffer[index] = camera.expose(accumulator[index] * frame_weight).to_u32();
                     }
                 }
             }
         );
 
-        camera.update_input(&window);
+        // Right clicking focuses on the object under the cursor
+        let mut focused = false;
+        if window.get_mouse_down(MouseButton::Right) {
+            if let Some((x, y)) = window.get_mouse_pos(MouseMode::Discard) {
+                focused = camera.focus_on(&scene, &screen, x as Float, y as Float);
+            }
+        }
+
+        // Long frames are capped so a stall does not send the camera flying
+        let frame_time = frame_start.elapsed().as_secs_f64().min(0.5) as Float;
+        frame_start = std::time::Instant::now();
+
+        let (moved, saved) = camera.update_input(&window, &mut controls, frame_time);
+        match saved {
+            Some(Ok(())) => println!("Saved bookmark {} to {}", controls.bookmarks.keyframes.len(), controls.bookmark_file),
+            Some(Err(error)) => println!("Unable to save the bookmarks: {}", error),
+            None => {}
+        }
+        if moved || focused {
+            accumulator.iter_mut().for_each(|color| *color = Color::black());
+            frame_count = 0;
+        }
         screen.update_screen(&camera);
 
         window